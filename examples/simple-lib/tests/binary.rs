@@ -0,0 +1,106 @@
+use simple_example_lib::{
+    backends,
+    binary::{self, Element},
+    Array_F64_1D, Config, Context,
+};
+
+type Backend = backends::C;
+
+fn round_trip<T>(value: T)
+where
+    T: Element + PartialEq + std::fmt::Debug,
+{
+    let mut bytes = Vec::new();
+    binary::write_scalar(&mut bytes, value).unwrap();
+    assert_eq!(
+        binary::read_scalar::<T>(&mut bytes.as_slice()).unwrap(),
+        value
+    );
+}
+
+#[test]
+fn scalars_round_trip() {
+    round_trip(-8i8);
+    round_trip(-16i16);
+    round_trip(-32i32);
+    round_trip(i64::MIN);
+    round_trip(8u8);
+    round_trip(16u16);
+    round_trip(32u32);
+    round_trip(u64::MAX);
+    round_trip(1.5f32);
+    round_trip(-2.25f64);
+}
+
+#[test]
+fn scalar_layout() {
+    let mut bytes = Vec::new();
+    binary::write_scalar(&mut bytes, 1i32).unwrap();
+
+    assert_eq!(bytes, b"b\x02\x00 i32\x01\x00\x00\x00");
+}
+
+#[test]
+fn f16_conversion() {
+    for (value, bits) in [
+        (1.5f32, 0x3e00u16),
+        (-2.0, 0xc000),
+        (65504.0, 0x7bff),
+        (f32::INFINITY, 0x7c00),
+        (5.960_464_5e-8, 0x0001),
+    ] {
+        let mut bytes = Vec::new();
+        value.encode("f16", &mut bytes);
+        assert_eq!(bytes, bits.to_le_bytes());
+        assert_eq!(f32::decode("f16", &bytes), value);
+    }
+}
+
+#[test]
+fn arrays_round_trip() {
+    let context = Context::new(Config::<Backend>::new());
+
+    let array = Array_F64_1D::new(&context, &[1.0, -2.5], 2);
+    let mut bytes = Vec::new();
+    array.write_binary(&mut bytes).unwrap();
+
+    let mut expected = b"b\x02\x01 f64".to_vec();
+    expected.extend(2u64.to_le_bytes());
+    expected.extend(1.0f64.to_le_bytes());
+    expected.extend((-2.5f64).to_le_bytes());
+    assert_eq!(bytes, expected);
+
+    let parsed = Array_F64_1D::read_binary(&context, &mut bytes.as_slice()).unwrap();
+    let mut values = Vec::new();
+    parsed.values(&mut values);
+    assert_eq!(values, [1.0, -2.5]);
+}
+
+#[test]
+fn consecutive_values() {
+    let context = Context::new(Config::<Backend>::new());
+
+    let mut bytes = Vec::new();
+    Array_F64_1D::new(&context, &[3.0], 1)
+        .write_binary(&mut bytes)
+        .unwrap();
+    bytes.push(b'\n');
+    binary::write_scalar(&mut bytes, 7i64).unwrap();
+
+    let mut reader = bytes.as_slice();
+    let array = Array_F64_1D::read_binary(&context, &mut reader).unwrap();
+    assert_eq!(array.shape(), [1]);
+    assert_eq!(binary::read_scalar::<i64>(&mut reader).unwrap(), 7);
+    assert!(reader.is_empty());
+}
+
+#[test]
+fn rejects_mismatched_values() {
+    let context = Context::new(Config::<Backend>::new());
+
+    let mut bytes = Vec::new();
+    binary::write_scalar(&mut bytes, 1.0f64).unwrap();
+    assert!(Array_F64_1D::read_binary(&context, &mut bytes.as_slice()).is_err());
+    assert!(binary::read_scalar::<f32>(&mut bytes.as_slice()).is_err());
+    assert!(binary::read_scalar::<f64>(&mut &bytes[..bytes.len() - 1]).is_err());
+}
@@ -0,0 +1,90 @@
+use simple_example_lib::{
+    backends::{self, Backend},
+    Array_F64_1D, Config, Context, EntryPoints,
+};
+
+/// Doubles `xs` and returns the mean, using any implementation of the entry points.
+fn doubled_mean<B: Backend>(entry_points: &impl EntryPoints<B>, xs: &Array_F64_1D<B>) -> f64 {
+    let doubled = entry_points.entry_double(xs).unwrap();
+    entry_points.entry_average(&doubled).unwrap()
+}
+
+#[test]
+fn context_implements_entry_points() {
+    let context = Context::new(Config::<backends::C>::new());
+    let xs = Array_F64_1D::new(&context, &[1.0, 2.0, 3.0], 3);
+
+    assert_eq!(doubled_mean(&context, &xs), 4.0);
+}
+
+#[cfg(feature = "test-backend")]
+mod with_test_backend {
+    use simple_example_lib::{
+        backends::{test, Test},
+        Array_F64_1D, Config, Context, EntryPoints, Error, Opaque_stats,
+    };
+
+    use super::doubled_mean;
+
+    /// Hand-written stand-in for the Futhark program.
+    ///
+    /// It uses a context of the test backend to create the values it returns.
+    struct Mock {
+        context: Context<Test>,
+    }
+
+    impl Mock {
+        fn new() -> Self {
+            Mock {
+                context: Context::new(Config::new()),
+            }
+        }
+    }
+
+    impl EntryPoints<Test> for Mock {
+        fn entry_average(&self, xs: &Array_F64_1D<Test>) -> Result<f64, Error> {
+            let xs = xs.to_vec()?;
+            Ok(xs.iter().sum::<f64>() / xs.len() as f64)
+        }
+
+        fn entry_double(&self, xs: &Array_F64_1D<Test>) -> Result<Array_F64_1D<Test>, Error> {
+            let doubled = xs.to_vec()?.iter().map(|x| x * 2.0).collect::<Vec<_>>();
+            Array_F64_1D::try_new(&self.context, &doubled, doubled.len())
+        }
+
+        fn entry_swap(&self, a: f64, b: f64) -> Result<(f64, f64), Error> {
+            Ok((b, a))
+        }
+
+        fn entry_stats(&self, xs: &Array_F64_1D<Test>) -> Result<Opaque_stats<Test>, Error> {
+            let stats = test::Opaque(Box::new(xs.to_vec()?));
+            Ok(Opaque_stats::from_test_value(&self.context, stats))
+        }
+
+        fn entry_stats_mean(&self, s: &Opaque_stats<Test>) -> Result<f64, Error> {
+            match s.test_value().0.downcast_ref::<Vec<f64>>() {
+                Some(xs) => Ok(xs.iter().sum::<f64>() / xs.len() as f64),
+                None => Err(Error::Status(1)),
+            }
+        }
+    }
+
+    #[test]
+    fn mock_returns_arrays() {
+        let mock = Mock::new();
+        let xs = Array_F64_1D::new(&mock.context, &[1.0, 2.0, 3.0], 3);
+
+        let doubled = mock.entry_double(&xs).unwrap();
+        assert_eq!(doubled.to_vec().unwrap(), [2.0, 4.0, 6.0]);
+        assert_eq!(doubled_mean(&mock, &xs), 4.0);
+    }
+
+    #[test]
+    fn mock_returns_opaque_values() {
+        let mock = Mock::new();
+        let xs = Array_F64_1D::new(&mock.context, &[1.0, 5.0], 2);
+
+        let stats = mock.entry_stats(&xs).unwrap();
+        assert_eq!(mock.entry_stats_mean(&stats).unwrap(), 3.0);
+    }
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "ndarray")]
+
+use ndarray::{array, s, Array1};
+use simple_example_lib::{backends, Array_F64_1D, Config, Context};
+
+type Backend = backends::C;
+
+#[test]
+fn from_standard_layout() {
+    let context = Context::new(Config::<Backend>::new());
+
+    let input: Array1<f64> = array![1.0, 2.0, 3.0];
+    let array = Array_F64_1D::from_ndarray(&context, &input).unwrap();
+
+    assert_eq!(array.shape(), [3]);
+    assert_eq!(array.to_ndarray().unwrap(), input);
+}
+
+#[test]
+fn from_strided_view() {
+    let context = Context::new(Config::<Backend>::new());
+
+    let input: Array1<f64> = array![1.0, 2.0, 3.0, 4.0, 5.0];
+    let view = input.slice(s![..;-2]);
+    let array = Array_F64_1D::from_ndarray(&context, &view).unwrap();
+
+    assert_eq!(array.to_ndarray().unwrap(), array![5.0, 3.0, 1.0]);
+}
+
+#[test]
+fn through_entry_point() {
+    let context = Context::new(Config::<Backend>::new());
+
+    let input = Array_F64_1D::from_ndarray(&context, &array![1.0, 2.0]).unwrap();
+    let doubled = context.entry_double(&input).unwrap();
+
+    assert_eq!(doubled.to_ndarray().unwrap(), array![2.0, 4.0]);
+}
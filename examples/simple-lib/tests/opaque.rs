@@ -0,0 +1,35 @@
+use simple_example_lib::{backends, Array_F64_1D, Config, Context, Opaque_stats};
+
+type Backend = backends::C;
+
+#[test]
+fn opaque_bytes_round_trip() {
+    let context = Context::new(Config::<Backend>::new());
+    let xs = Array_F64_1D::new(&context, &[1.0, 2.0, 6.0], 3);
+
+    let stats = context.entry_stats(&xs).unwrap();
+    let bytes = stats.to_bytes().unwrap();
+
+    // The bytes were just created by `to_bytes`.
+    let restored = unsafe { Opaque_stats::from_bytes(&context, &bytes) }.unwrap();
+    assert_eq!(context.entry_stats_mean(&restored).unwrap(), 3.0);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn opaque_serde_round_trip() {
+    use serde::de::DeserializeSeed;
+
+    let context = Context::new(Config::<Backend>::new());
+    let xs = Array_F64_1D::new(&context, &[2.0, 4.0], 2);
+
+    let stats = context.entry_stats(&xs).unwrap();
+    let json = serde_json::to_string(&stats).unwrap();
+
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    // The JSON was just serialized from the same type.
+    let restored = unsafe { Opaque_stats::seed(&context) }
+        .deserialize(&mut deserializer)
+        .unwrap();
+    assert_eq!(context.entry_stats_mean(&restored).unwrap(), 3.0);
+}
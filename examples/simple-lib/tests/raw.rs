@@ -0,0 +1,33 @@
+#![cfg(feature = "array-ops")]
+
+use std::mem::ManuallyDrop;
+
+use simple_example_lib::{backends, Array_F64_1D, Config, Context};
+
+type Backend = backends::C;
+
+#[test]
+fn values_raw_is_host_pointer() {
+    let context = Context::new(Config::<Backend>::new());
+    let array = Array_F64_1D::new(&context, &[1.0, 2.0, 3.0], 3);
+
+    let values = unsafe {
+        let data = array.values_raw();
+        assert!(context.sync());
+        std::slice::from_raw_parts(data as *const f64, 3).to_vec()
+    };
+
+    assert_eq!(values, [1.0, 2.0, 3.0]);
+}
+
+#[test]
+fn new_raw_from_host_pointer() {
+    let context = Context::new(Config::<Backend>::new());
+    let mut data = ManuallyDrop::new(vec![4.0f64, 5.0]);
+
+    let array =
+        unsafe { Array_F64_1D::new_raw(&context, data.as_mut_ptr() as *mut u8, 2) }.unwrap();
+
+    assert_eq!(array.shape(), [2]);
+    assert_eq!(array.to_vec().unwrap(), [4.0, 5.0]);
+}
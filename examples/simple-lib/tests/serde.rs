@@ -0,0 +1,51 @@
+#![cfg(feature = "serde")]
+
+use serde::de::DeserializeSeed;
+use simple_example_lib::{backends, Array_F64_1D, Config, Context};
+
+type Backend = backends::C;
+
+#[test]
+fn serialize_array() {
+    let context = Context::new(Config::<Backend>::new());
+    let array = Array_F64_1D::new(&context, &[1.0, 2.5], 2);
+
+    let json = serde_json::to_string(&array).unwrap();
+    assert_eq!(json, r#"{"shape":[2],"data":[1.0,2.5]}"#);
+}
+
+#[test]
+fn array_serde_round_trip() {
+    let context = Context::new(Config::<Backend>::new());
+    let array = Array_F64_1D::new(&context, &[1.0, 2.0, 3.0], 3);
+
+    let json = serde_json::to_string(&array).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let restored = Array_F64_1D::seed(&context)
+        .deserialize(&mut deserializer)
+        .unwrap();
+
+    assert_eq!(restored.shape(), [3]);
+    assert_eq!(restored.to_vec().unwrap(), [1.0, 2.0, 3.0]);
+}
+
+#[test]
+fn deserialize_array_with_wrong_rank() {
+    let context = Context::new(Config::<Backend>::new());
+
+    let mut deserializer =
+        serde_json::Deserializer::from_str(r#"{"shape":[1,2],"data":[1.0,2.0]}"#);
+    assert!(Array_F64_1D::seed(&context)
+        .deserialize(&mut deserializer)
+        .is_err());
+}
+
+#[test]
+fn deserialize_array_with_wrong_length() {
+    let context = Context::new(Config::<Backend>::new());
+
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{"shape":[3],"data":[1.0]}"#);
+    assert!(Array_F64_1D::seed(&context)
+        .deserialize(&mut deserializer)
+        .is_err());
+}
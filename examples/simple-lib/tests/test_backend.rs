@@ -0,0 +1,90 @@
+#![cfg(feature = "test-backend")]
+
+use simple_example_lib::{
+    backends::{self, test},
+    Array_F64_1D, Config, Context, Error,
+};
+
+type Backend = backends::Test;
+
+#[test]
+fn entry_points_call_handlers() {
+    let config = Config::<Backend>::new()
+        .on_entry_average(|xs| Ok(xs.data.iter().sum::<f64>() / xs.data.len() as f64))
+        .on_entry_double(|xs| {
+            let data = xs.data.iter().map(|x| x * 2.0).collect();
+            Ok(test::Array::new(data, xs.shape.clone()))
+        });
+    let context = Context::new(config);
+
+    let xs = Array_F64_1D::new(&context, &[1.0, 2.0, 3.0], 3);
+    assert_eq!(context.entry_average(&xs).unwrap(), 2.0);
+
+    let doubled = context.entry_double(&xs).unwrap();
+    assert_eq!(doubled.shape(), [3]);
+    assert_eq!(doubled.to_vec().unwrap(), [2.0, 4.0, 6.0]);
+}
+
+#[test]
+fn handler_errors_are_returned() {
+    let config = Config::<Backend>::new().on_entry_swap(|_, _| Err(3));
+    let context = Context::new(config);
+
+    assert_eq!(context.entry_swap(1.0, 2.0), Err(Error::Status(3)));
+}
+
+#[test]
+fn handler_errors_are_never_success() {
+    let config = Config::<Backend>::new().on_entry_swap(|_, _| Err(0));
+    let context = Context::new(config);
+
+    assert_eq!(context.entry_swap(1.0, 2.0), Err(Error::Status(1)));
+}
+
+#[test]
+fn mismatched_arrays_fail() {
+    let config = Config::<Backend>::new().on_entry_double(|_| {
+        Ok(test::Array {
+            shape: vec![5],
+            data: vec![1.0],
+        })
+    });
+    let context = Context::new(config);
+
+    let xs = Array_F64_1D::new(&context, &[1.0], 1);
+    assert!(matches!(context.entry_double(&xs), Err(Error::Status(1))));
+}
+
+#[test]
+fn missing_handler_fails() {
+    let context = Context::new(Config::<Backend>::new());
+
+    assert_eq!(context.entry_swap(1.0, 2.0), Err(Error::Status(1)));
+}
+
+#[test]
+fn opaque_values_are_passed_through() {
+    let config = Config::<Backend>::new()
+        .on_entry_stats(|xs| Ok(test::Opaque(Box::new(xs.data.clone()))))
+        .on_entry_stats_mean(|s| {
+            let xs = s.0.downcast_ref::<Vec<f64>>().unwrap();
+            Ok(xs.iter().sum::<f64>() / xs.len() as f64)
+        });
+    let context = Context::new(config);
+
+    let xs = Array_F64_1D::new(&context, &[1.0, 5.0], 2);
+    let stats = context.entry_stats(&xs).unwrap();
+
+    assert_eq!(context.entry_stats_mean(&stats).unwrap(), 3.0);
+    assert!(stats.to_bytes().is_err());
+}
+
+#[test]
+#[cfg(feature = "array-ops")]
+fn index_is_bounds_checked() {
+    let context = Context::new(Config::<Backend>::new());
+    let xs = Array_F64_1D::new(&context, &[1.0, 2.0], 2);
+
+    assert_eq!(xs.get([1]).unwrap(), 2.0);
+    assert!(matches!(xs.get([2]), Err(Error::IndexOutOfBounds { .. })));
+}
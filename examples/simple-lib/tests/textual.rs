@@ -0,0 +1,86 @@
+use simple_example_lib::{
+    backends,
+    textual::{self, Value},
+    Array_F64_1D, Config, Context,
+};
+
+type Backend = backends::C;
+
+fn round_trip<T>(value: T, text: &str)
+where
+    T: textual::Scalar + PartialEq + std::fmt::Debug,
+{
+    assert_eq!(Value(value).to_string(), text);
+    assert_eq!(text.parse::<Value<T>>().unwrap(), Value(value));
+}
+
+#[test]
+fn scalars_round_trip() {
+    round_trip(-8i8, "-8i8");
+    round_trip(-16i16, "-16i16");
+    round_trip(-32i32, "-32i32");
+    round_trip(i64::MIN, "-9223372036854775808i64");
+    round_trip(8u8, "8u8");
+    round_trip(16u16, "16u16");
+    round_trip(32u32, "32u32");
+    round_trip(u64::MAX, "18446744073709551615u64");
+    round_trip(1.5f32, "1.5f32");
+    round_trip(-2.0f64, "-2.0f64");
+    round_trip(f64::INFINITY, "f64.inf");
+    round_trip(f32::NEG_INFINITY, "-f32.inf");
+}
+
+#[test]
+fn scalars_parse_without_suffix() {
+    assert_eq!("42".parse::<Value<i32>>().unwrap(), Value(42));
+    assert_eq!(" 1.25 ".parse::<Value<f64>>().unwrap(), Value(1.25));
+}
+
+#[test]
+fn scalars_reject_other_suffix() {
+    assert!("1i64".parse::<Value<i32>>().is_err());
+    assert!("1.0f32".parse::<Value<f64>>().is_err());
+    assert!("1.0".parse::<Value<u8>>().is_err());
+}
+
+#[test]
+fn nan_round_trips() {
+    assert_eq!(Value(f64::NAN).to_string(), "f64.nan");
+    assert!("f64.nan".parse::<Value<f64>>().unwrap().0.is_nan());
+}
+
+#[test]
+fn arrays_round_trip() {
+    let context = Context::new(Config::<Backend>::new());
+
+    let array = Array_F64_1D::new(&context, &[1.0, -2.5, 3.0], 3);
+    let text = array.to_string();
+    assert_eq!(text, "[1.0f64, -2.5f64, 3.0f64]");
+
+    let parsed = Array_F64_1D::from_textual(&context, &text).unwrap();
+    assert_eq!(parsed.to_textual().unwrap(), text);
+}
+
+#[test]
+fn empty_arrays_round_trip() {
+    let context = Context::new(Config::<Backend>::new());
+
+    let array = Array_F64_1D::new(&context, &[], 0);
+    assert_eq!(array.to_string(), "empty([0]f64)");
+
+    let parsed = Array_F64_1D::from_textual(&context, "empty([0]f64)").unwrap();
+    assert_eq!(parsed.shape(), [0]);
+
+    let parsed = Array_F64_1D::from_textual(&context, "[]").unwrap();
+    assert_eq!(parsed.shape(), [0]);
+}
+
+#[test]
+fn arrays_reject_invalid_input() {
+    let context = Context::new(Config::<Backend>::new());
+
+    assert!(Array_F64_1D::from_textual(&context, "[1.0, 2.0").is_err());
+    assert!(Array_F64_1D::from_textual(&context, "[[1.0]]").is_err());
+    assert!(Array_F64_1D::from_textual(&context, "[1.0i32]").is_err());
+    assert!(Array_F64_1D::from_textual(&context, "[1.0] 2.0").is_err());
+}
@@ -0,0 +1,30 @@
+use simple_example_lib::{backends, Array_F64_1D, Config, Context, FutharkArray};
+
+type Backend = backends::C;
+
+fn reversed<'c, A: FutharkArray<'c, Backend>>(array: &A) -> A {
+    let mut values = array.to_vec().unwrap();
+    values.reverse();
+
+    A::try_from_shape(array.context(), &values, array.shape()).unwrap()
+}
+
+fn describe<'c, A: FutharkArray<'c, Backend>>(array: &A) -> String {
+    format!("[{}]{} {:?}", A::RANK, A::ELEM_NAME, array.shape())
+}
+
+#[test]
+fn generic_over_arrays() {
+    let context = Context::new(Config::<Backend>::new());
+    let array = Array_F64_1D::new(&context, &[1.0, 2.0, 3.0], 3);
+
+    assert_eq!(reversed(&array).to_vec().unwrap(), [3.0, 2.0, 1.0]);
+    assert_eq!(describe(&array), "[1]f64 [3]");
+}
+
+#[test]
+fn from_shape_checks_length() {
+    let context = Context::new(Config::<Backend>::new());
+
+    assert!(Array_F64_1D::try_from_shape(&context, &[1.0], [2]).is_err());
+}
@@ -0,0 +1,91 @@
+use simple_example_lib::{backends, Array_F64_1D, Config, Context, Error};
+
+type Backend = backends::C;
+
+#[test]
+fn values_into_checks_length() {
+    let context = Context::new(Config::<Backend>::new());
+    let array = Array_F64_1D::new(&context, &[1.0, 2.0, 3.0], 3);
+
+    let mut out = [0.0; 3];
+    array.values_into(&mut out).unwrap();
+    assert_eq!(out, [1.0, 2.0, 3.0]);
+
+    let mut short = [0.0; 2];
+    assert_eq!(
+        array.values_into(&mut short),
+        Err(Error::LengthMismatch {
+            expected: 3,
+            actual: 2
+        })
+    );
+}
+
+#[test]
+fn to_vec() {
+    let context = Context::new(Config::<Backend>::new());
+    let array = Array_F64_1D::new(&context, &[4.0, 5.0], 2);
+
+    assert_eq!(array.to_vec().unwrap(), [4.0, 5.0]);
+}
+
+#[test]
+fn values_reuses_buffer_with_capacity() {
+    let context = Context::new(Config::<Backend>::new());
+    let array = Array_F64_1D::new(&context, &[1.0, 2.0], 2);
+
+    let mut out = Vec::with_capacity(16);
+    out.extend([9.0; 5]);
+    array.values(&mut out);
+    assert_eq!(out, [1.0, 2.0]);
+}
+
+#[test]
+fn unsynced_values_after_sync() {
+    let context = Context::new(Config::<Backend>::new());
+    let a = Array_F64_1D::new(&context, &[1.0], 1);
+    let b = Array_F64_1D::new(&context, &[2.0, 3.0], 2);
+
+    let mut out_a = [0.0; 1];
+    let mut out_b = [0.0; 2];
+    unsafe {
+        a.values_into_unsynced(&mut out_a).unwrap();
+        b.values_into_unsynced(&mut out_b).unwrap();
+    }
+    assert!(context.sync());
+
+    assert_eq!(out_a, [1.0]);
+    assert_eq!(out_b, [2.0, 3.0]);
+}
+
+#[test]
+fn try_new_checks_dimensions() {
+    let context = Context::new(Config::<Backend>::new());
+
+    assert_eq!(
+        Array_F64_1D::try_new(&context, &[1.0, 2.0], 3).err(),
+        Some(Error::LengthMismatch {
+            expected: 3,
+            actual: 2
+        })
+    );
+
+    let array = Array_F64_1D::try_new(&context, &[1.0, 2.0], 2).unwrap();
+    assert_eq!(array.shape(), [2]);
+}
+
+#[test]
+#[cfg(feature = "array-ops")]
+fn get_single_element() {
+    let context = Context::new(Config::<Backend>::new());
+    let array = Array_F64_1D::new(&context, &[1.0, 2.0, 3.0], 3);
+
+    assert_eq!(array.get([1]), Ok(2.0));
+    assert_eq!(
+        array.get([3]),
+        Err(Error::IndexOutOfBounds {
+            index: vec![3],
+            shape: vec![3]
+        })
+    );
+}
@@ -0,0 +1,162 @@
+use std::{
+    fmt::Write as _,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use enumflags2::BitFlags;
+use eyre::{ensure, eyre, Context, Result};
+use proc_macro2::TokenStream;
+
+use crate::{
+    manifest::{EntryPoint, Manifest, Type},
+    template, Target,
+};
+
+/// Generated bindings of a Futhark program, as returned by [`Generator::bindings`](crate::Generator::bindings).
+///
+/// This gives access to the generated code without building it,
+/// which helps when debugging the bindings.
+pub struct Bindings {
+    pub(crate) manifest: Manifest,
+    pub(crate) targets: BitFlags<Target>,
+    pub(crate) options: template::Options,
+    pub(crate) diagnostics: String,
+}
+
+impl Bindings {
+    /// Warnings reported by the Futhark compiler.
+    pub fn diagnostics(&self) -> &str {
+        &self.diagnostics
+    }
+
+    /// The complete generated library, formatted by `rustfmt`.
+    pub fn code(&self) -> Result<String> {
+        format(template::combined(
+            &self.manifest,
+            self.targets,
+            &self.options,
+        ))
+    }
+
+    /// The code generated for a single entry point or type, formatted by `rustfmt`.
+    ///
+    /// Types can be named like in Futhark (`[]f32`) or like the generated struct (`Array_F32_1D`).
+    pub fn item(&self, name: &str) -> Result<String> {
+        let item = template::item(&self.manifest, name, &self.options).ok_or_else(|| {
+            eyre!(
+                "There is no entry point or type called `{name}`, try one of: {}.",
+                self.item_names().join(", ")
+            )
+        })?;
+
+        format(item)
+    }
+
+    /// A human-readable summary of the entry points and types in the Futhark manifest.
+    pub fn summary(&self) -> String {
+        let mut summary = String::from("Entry points:\n");
+
+        for ep in &self.manifest.entry_points {
+            let _ = writeln!(summary, "  {}", signature(ep));
+
+            if let Some(doc) = ep.doc.as_deref().and_then(|doc| doc.lines().next()) {
+                let _ = writeln!(summary, "    {}", doc.trim());
+            }
+            match ep.tests.len() {
+                0 => {}
+                1 => summary.push_str("    1 test case\n"),
+                n => {
+                    let _ = writeln!(summary, "    {n} test cases");
+                }
+            }
+        }
+
+        summary.push_str("\nTypes:\n");
+
+        for typ in self.types() {
+            let (kind, rust_name) = match typ {
+                Type::Value(_) => continue,
+                Type::Array(array) => ("array", array.struct_ident()),
+                Type::Opaque(opaque) => ("opaque", opaque.struct_ident()),
+            };
+
+            let _ = writeln!(summary, "  {kind} {} as {rust_name}", typ.name());
+        }
+
+        summary
+    }
+
+    fn types(&self) -> Vec<&Type> {
+        let mut types = self.manifest.types.iter().collect::<Vec<_>>();
+        types.sort_by_key(|typ| typ.name());
+        types
+    }
+
+    fn item_names(&self) -> Vec<String> {
+        self.manifest
+            .entry_points
+            .iter()
+            .map(|ep| ep.name.clone())
+            .chain(
+                self.types()
+                    .into_iter()
+                    .filter(|typ| !matches!(typ, Type::Value(_)))
+                    .map(Type::name),
+            )
+            .collect()
+    }
+}
+
+/// Returns the Futhark signature of `ep`, like `average (xs: []f64): f64`.
+fn signature(ep: &EntryPoint) -> String {
+    let inputs = ep
+        .inputs
+        .iter()
+        .map(|input| match &input.name {
+            Some(name) => format!("({name}: {})", input.typ.name()),
+            None => input.typ.name(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let outputs = ep
+        .outputs
+        .iter()
+        .map(|output| output.typ.name())
+        .collect::<Vec<_>>();
+    let outputs = match outputs.as_slice() {
+        [output] => output.clone(),
+        outputs => format!("({})", outputs.join(", ")),
+    };
+
+    format!("{} {inputs}: {outputs}", ep.name)
+}
+
+fn format(code: TokenStream) -> Result<String> {
+    let mut rustfmt = Command::new("rustfmt")
+        .args(["--edition", "2021"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .wrap_err("Failed to run rustfmt.")?;
+
+    rustfmt
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(code.to_string().as_bytes())
+        .wrap_err("Failed to pass the code to rustfmt.")?;
+
+    let output = rustfmt
+        .wait_with_output()
+        .wrap_err("Failed to run rustfmt.")?;
+    ensure!(
+        output.status.success(),
+        "Failed to format generated code:\n{}",
+        String::from_utf8_lossy(&output.stderr).trim_end()
+    );
+
+    String::from_utf8(output.stdout).wrap_err("rustfmt returned invalid UTF-8.")
+}
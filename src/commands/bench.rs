@@ -0,0 +1,204 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use eyre::{ensure, eyre, Context, Result};
+use serde_json::{json, Value};
+
+use crate::commands::{
+    project::{self, Project},
+    template::Backend,
+    test,
+};
+
+/// Runs the benchmarks generated from the `-- ==` blocks of the Futhark source
+/// and saves the results as JSON.
+///
+/// Results are compared with the report at `compare` if it is given.
+pub fn bench(
+    manifest_path: Option<&Path>,
+    backends: &[Backend],
+    runs: usize,
+    warmup: usize,
+    output: Option<&Path>,
+    compare: Option<&Path>,
+    args: &[String],
+) -> Result<()> {
+    ensure!(runs > 0, "At least one run is required.");
+
+    let project = Project::find(manifest_path)?;
+    let baseline = compare.map(load_report).transpose()?;
+
+    let results_path = env::temp_dir().join(format!("cargo-futhark-bench-{}.jsonl", process::id()));
+    // A leftover file would mix old results into the report.
+    let _ = fs::remove_file(&results_path);
+
+    let mut cargo = project::cargo();
+    cargo
+        .arg("test")
+        .arg("--release")
+        .arg("--manifest-path")
+        .arg(project.manifest_path())
+        .env("CARGO_FUTHARK_BENCH_RUNS", runs.to_string())
+        .env("CARGO_FUTHARK_BENCH_WARMUP", warmup.to_string())
+        .env("CARGO_FUTHARK_BENCH_OUTPUT", &results_path);
+    test::select_tests(
+        &mut cargo,
+        "futhark_bench",
+        backends,
+        args,
+        &["--ignored", "--test-threads=1"],
+    );
+
+    let status = cargo.status().wrap_err("Failed to run `cargo test`.")?;
+    let lines = fs::read_to_string(&results_path).unwrap_or_default();
+    let _ = fs::remove_file(&results_path);
+    ensure!(status.success(), "Some Futhark benchmarks failed.");
+
+    let results = lines
+        .lines()
+        .map(summarize)
+        .collect::<Result<Vec<_>>>()
+        .wrap_err("Failed to read benchmark results.")?;
+    ensure!(
+        !results.is_empty(),
+        "No benchmarks were found, add `-- ==` blocks with inputs to the Futhark source."
+    );
+
+    println!();
+    for result in &results {
+        print_result(result, baseline.as_ref());
+    }
+
+    let report = json!({
+        "commit": git_commit(&project.root),
+        "runs": runs,
+        "warmup": warmup,
+        "results": results,
+    });
+
+    let output = match output {
+        Some(output) => output.to_path_buf(),
+        None => default_output(&project, &report),
+    };
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).wrap_err("Failed to create report directory.")?;
+    }
+    fs::write(&output, serde_json::to_string_pretty(&report)?)
+        .wrap_err("Failed to write benchmark report.")?;
+
+    println!();
+    println!("Saved the results to {}.", output.display());
+
+    let failed = results
+        .iter()
+        .filter(|result| result.get("error").is_some())
+        .count();
+    ensure!(failed == 0, "{failed} Futhark benchmarks failed.");
+    ensure!(
+        results.iter().any(|result| result.get("mean_us").is_some()),
+        "None of the datasets could be benchmarked, see the skipped ones above."
+    );
+
+    Ok(())
+}
+
+/// Adds the mean runtime and its 95% confidence interval to a result line.
+///
+/// Results of failed or skipped benchmarks only contain their `error` or `skipped` reason.
+fn summarize(line: &str) -> Result<Value> {
+    let mut result = serde_json::from_str::<Value>(line)?;
+    if result.get("error").is_some() || result.get("skipped").is_some() {
+        return Ok(result);
+    }
+
+    let runtimes = result["runtimes_us"]
+        .as_array()
+        .ok_or_else(|| eyre!("Missing runtimes."))?
+        .iter()
+        .filter_map(Value::as_f64)
+        .collect::<Vec<_>>();
+    ensure!(!runtimes.is_empty(), "Missing runtimes.");
+
+    let n = runtimes.len() as f64;
+    let mean = runtimes.iter().sum::<f64>() / n;
+    let stddev = if runtimes.len() > 1 {
+        let variance = runtimes.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        variance.sqrt()
+    } else {
+        0.0
+    };
+
+    result["mean_us"] = json!(mean);
+    result["stddev_us"] = json!(stddev);
+    result["ci95_us"] = json!(1.96 * stddev / n.sqrt());
+
+    Ok(result)
+}
+
+fn print_result(result: &Value, baseline: Option<&Value>) {
+    let name = format!(
+        "{} {} #{}",
+        result["backend"].as_str().unwrap_or_default(),
+        result["entry"].as_str().unwrap_or_default(),
+        result["dataset"]
+    );
+    if let Some(error) = result["error"].as_str() {
+        println!("{name:<32} failed: {error}");
+        return;
+    }
+    if let Some(reason) = result["skipped"].as_str() {
+        println!("{name:<32} skipped: {reason}");
+        return;
+    }
+
+    let mean = result["mean_us"].as_f64().unwrap_or_default();
+    let ci95 = result["ci95_us"].as_f64().unwrap_or_default();
+
+    let change = baseline
+        .and_then(|baseline| baseline["results"].as_array())
+        .and_then(|results| {
+            results.iter().find(|old| {
+                ["backend", "entry", "dataset"]
+                    .iter()
+                    .all(|key| old[key] == result[key])
+            })
+        })
+        .and_then(|old| old["mean_us"].as_f64())
+        .map(|old_mean| format!(" ({:+.1}%)", (mean / old_mean - 1.0) * 100.0))
+        .unwrap_or_default();
+
+    println!("{name:<32} {mean:>12.1}µs ± {ci95:.1}µs{change}");
+}
+
+fn load_report(path: &Path) -> Result<Value> {
+    let content =
+        fs::read_to_string(path).wrap_err_with(|| format!("Failed to read {}.", path.display()))?;
+
+    serde_json::from_str(&content).wrap_err_with(|| format!("Failed to parse {}.", path.display()))
+}
+
+fn git_commit(root: &Path) -> Option<String> {
+    let output = process::Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+
+    let commit = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !commit.is_empty()).then_some(commit)
+}
+
+/// Returns `target/futhark-bench/<commit>.json` in the project.
+fn default_output(project: &Project, report: &Value) -> PathBuf {
+    let name = report["commit"].as_str().unwrap_or("latest");
+
+    project
+        .root
+        .join("target")
+        .join("futhark-bench")
+        .join(format!("{name}.json"))
+}
@@ -0,0 +1,94 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use cargo_futhark::Generator;
+use clap::Args;
+use eyre::{Context, Result};
+
+use crate::commands::{project::Project, template::Backend};
+
+/// Options of the generated code, mirroring the [`Generator`] settings of the build script.
+#[derive(Debug, Clone, Copy, Args)]
+pub struct CodeOptions {
+    /// Return structs from entry points with multiple outputs.
+    #[arg(long)]
+    pub output_structs: bool,
+    /// Generate conversions to and from `ndarray`.
+    #[arg(long)]
+    pub ndarray: bool,
+    /// Generate `serde` support.
+    #[arg(long)]
+    pub serde: bool,
+    /// Generate the pure-Rust `Test` backend.
+    #[arg(long)]
+    pub test_backend: bool,
+}
+
+/// What `cargo futhark bindings` prints.
+#[derive(Debug, Clone, Copy)]
+pub enum Show<'a> {
+    /// The complete generated library.
+    Code,
+    /// Only the code of one entry point or type.
+    Item(&'a str),
+    /// A summary of the Futhark manifest.
+    Manifest,
+}
+
+/// Generates the bindings of `source`, or of the project's Futhark source,
+/// and prints them or writes them to `output`.
+pub fn bindings(
+    source: Option<&Path>,
+    manifest_path: Option<&Path>,
+    backends: &[Backend],
+    options: CodeOptions,
+    show: Show,
+    output: Option<&Path>,
+) -> Result<()> {
+    let source = match source {
+        Some(source) => source.to_path_buf(),
+        None => Project::find(manifest_path)?.source,
+    };
+
+    let mut generator = Generator::new(source);
+    generator
+        .output_structs(options.output_structs)
+        .ndarray(options.ndarray)
+        .serde(options.serde)
+        .test_backend(options.test_backend);
+
+    if backends.is_empty() {
+        generator.with_target(Backend::C.target());
+    }
+    for backend in backends {
+        generator.with_target(backend.target());
+    }
+
+    let out_dir = temp_dir();
+    let bindings = generator.bindings(&out_dir);
+    let _ = fs::remove_dir_all(&out_dir);
+    let bindings = bindings?;
+
+    eprint!("{}", bindings.diagnostics());
+
+    let text = match show {
+        Show::Code => bindings.code()?,
+        Show::Item(name) => bindings.item(name)?,
+        Show::Manifest => bindings.summary(),
+    };
+
+    match output {
+        Some(output) => fs::write(output, text)
+            .wrap_err_with(|| format!("Failed to write {}.", output.display()))?,
+        None => print!("{text}"),
+    }
+
+    Ok(())
+}
+
+fn temp_dir() -> PathBuf {
+    env::temp_dir().join(format!("cargo-futhark-bindings-{}", process::id()))
+}
@@ -0,0 +1,54 @@
+use std::{path::Path, process::Command};
+
+use eyre::{ensure, Context, Result};
+
+use crate::commands::project::{self, Project};
+
+/// Runs `futhark check` on the entry file of the project.
+pub fn check(manifest_path: Option<&Path>) -> Result<()> {
+    let project = Project::find(manifest_path)?;
+    check_source(&project)?;
+
+    println!("{} has no errors.", project.source.display());
+
+    Ok(())
+}
+
+/// Checks the Futhark code and runs `cargo build` with `args` if it is fine.
+pub fn build(manifest_path: Option<&Path>, args: &[String]) -> Result<()> {
+    let project = Project::find(manifest_path)?;
+    check_source(&project)?;
+
+    let status = project::cargo()
+        .arg("build")
+        .arg("--manifest-path")
+        .arg(project.manifest_path())
+        .args(args)
+        .status()
+        .wrap_err("Failed to run `cargo build`.")?;
+    ensure!(status.success(), "`cargo build` failed.");
+
+    Ok(())
+}
+
+fn check_source(project: &Project) -> Result<()> {
+    ensure!(
+        project.source.is_file(),
+        "The Futhark source file {} does not exist.",
+        project.source.display()
+    );
+
+    // Futhark prints its diagnostics to stderr, which we pass through unchanged.
+    let status = Command::new("futhark")
+        .arg("check")
+        .arg(&project.source)
+        .status()
+        .wrap_err("Failed to run `futhark check`, is Futhark installed?")?;
+    ensure!(
+        status.success(),
+        "{} contains errors.",
+        project.source.display()
+    );
+
+    Ok(())
+}
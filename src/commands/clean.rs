@@ -0,0 +1,128 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{eyre, Context, Result};
+
+use crate::commands::project::{self, Project};
+
+/// Removes the Futhark build artifacts of the package, without a full `cargo clean`.
+///
+/// Build script outputs containing `futhark` or `futhark_raw` directories are removed
+/// together with their fingerprints, so Cargo reruns the build script on the next build.
+/// Futhark cache files are only removed if they are passed in `cache_files`,
+/// since their location depends on how the program is run.
+pub fn clean(manifest_path: Option<&Path>, cache_files: &[PathBuf], dry_run: bool) -> Result<()> {
+    let project = Project::find(manifest_path)?;
+    let (target_dir, package) = target_dir_and_package(&project)?;
+
+    let mut paths = build_artifacts(&target_dir, &package);
+    paths.extend(cache_files.iter().filter(|path| path.exists()).cloned());
+
+    if paths.is_empty() {
+        println!("Nothing to clean.");
+        return Ok(());
+    }
+
+    for path in &paths {
+        if dry_run {
+            println!("Would remove {}", path.display());
+            continue;
+        }
+
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+        removed.wrap_err_with(|| format!("Failed to remove {}.", path.display()))?;
+        println!("Removed {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Asks `cargo metadata` for the target directory and the name of the package.
+fn target_dir_and_package(project: &Project) -> Result<(PathBuf, String)> {
+    let manifest_path = project.manifest_path();
+    let metadata = project::metadata(&manifest_path)?;
+
+    let target_dir = metadata["target_directory"]
+        .as_str()
+        .map(PathBuf::from)
+        .ok_or_else(|| eyre!("`cargo metadata` did not report a target directory."))?;
+
+    let manifest_path = manifest_path
+        .canonicalize()
+        .wrap_err_with(|| format!("Failed to find {}.", manifest_path.display()))?;
+    let package = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|package| {
+            package["manifest_path"]
+                .as_str()
+                .and_then(|path| Path::new(path).canonicalize().ok())
+                .is_some_and(|path| path == manifest_path)
+        })
+        .and_then(|package| package["name"].as_str())
+        .ok_or_else(|| eyre!("`cargo metadata` did not report the package."))?;
+
+    Ok((target_dir, package.to_string()))
+}
+
+/// Returns the build script outputs and fingerprints of `package` containing Futhark artifacts.
+///
+/// Profiles live in `target/<profile>` or, when cross compiling, in `target/<triple>/<profile>`.
+fn build_artifacts(target_dir: &Path, package: &str) -> Vec<PathBuf> {
+    let profile_dirs = subdirs(target_dir)
+        .into_iter()
+        .flat_map(|dir| {
+            let mut dirs = subdirs(&dir);
+            dirs.push(dir);
+            dirs
+        })
+        .filter(|dir| dir.join("build").is_dir());
+
+    let mut artifacts = Vec::new();
+    for profile_dir in profile_dirs {
+        for build_dir in subdirs(&profile_dir.join("build")) {
+            let Some(unit) = build_dir.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let is_package = unit
+                .strip_prefix(package)
+                .and_then(|hash| hash.strip_prefix('-'))
+                .is_some_and(|hash| hash.chars().all(|c| c.is_ascii_hexdigit()));
+
+            let out_dir = build_dir.join("out");
+            let has_futhark = ["futhark", "futhark_raw"]
+                .iter()
+                .any(|dir| out_dir.join(dir).is_dir());
+
+            if is_package && has_futhark {
+                let fingerprint = profile_dir.join(".fingerprint").join(unit);
+                artifacts.push(build_dir);
+                if fingerprint.is_dir() {
+                    artifacts.push(fingerprint);
+                }
+            }
+        }
+    }
+
+    artifacts.sort();
+    artifacts
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+    dirs.sort();
+    dirs
+}
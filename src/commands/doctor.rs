@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use cargo_futhark::toolchain::{self, Requirement};
+use clap::ValueEnum;
+use eyre::{ensure, Result};
+
+use crate::commands::template::Backend;
+
+/// Checks whether the tools and libraries needed by each backend can be found.
+///
+/// Only a missing common dependency, or a missing dependency of one of the
+/// explicitly requested `backends`, is an error.
+/// `cuda_home` is searched first for the CUDA SDK.
+pub fn doctor(backends: &[Backend], cuda_home: Option<&Path>) -> Result<()> {
+    let mut missing = 0;
+
+    println!("Common:");
+    missing += print_requirements(&toolchain::check_common());
+
+    let checked = if backends.is_empty() {
+        Backend::value_variants()
+    } else {
+        backends
+    };
+
+    for backend in checked {
+        let requirements = toolchain::check_target(backend.target(), cuda_home);
+        println!();
+        println!("{}:", backend.feature());
+
+        if requirements.is_empty() {
+            println!("  ✔ nothing beyond the common dependencies");
+        }
+
+        let backend_missing = print_requirements(&requirements);
+        if !backends.is_empty() {
+            missing += backend_missing;
+        }
+    }
+
+    println!();
+    ensure!(
+        missing == 0,
+        "{missing} required {} could not be found.",
+        if missing == 1 {
+            "dependency"
+        } else {
+            "dependencies"
+        }
+    );
+    println!("Everything needed is installed.");
+
+    Ok(())
+}
+
+/// Prints the requirements and returns how many of them are missing.
+fn print_requirements(requirements: &[Requirement]) -> usize {
+    for requirement in requirements {
+        match &requirement.found {
+            Some(found) => println!("  ✔ {}: {found}", requirement.name),
+            None => {
+                println!("  ✘ {}: not found", requirement.name);
+                println!("    {}", requirement.hint);
+
+                if !requirement.searched.is_empty() {
+                    println!("    Searched:");
+                    for dir in &requirement.searched {
+                        println!("      {}", dir.display());
+                    }
+                }
+            }
+        }
+    }
+
+    requirements.iter().filter(|r| r.is_missing()).count()
+}
@@ -0,0 +1,250 @@
+use std::{fs, path::Path};
+
+use eyre::{bail, ensure, eyre, Context, Result};
+use toml_edit::{DocumentMut, Item};
+
+use clap::ValueEnum;
+
+use crate::commands::{
+    project,
+    template::{Backend, Variables},
+    LIB_TEMPLATE,
+};
+
+const INCLUDE_LINE: &str = r#"include!(concat!(env!("OUT_DIR"), "/futhark/futhark_lib.rs"));"#;
+
+/// Binaries include the bindings in a module, like the `bin` template,
+/// to avoid warnings about unused items.
+const INCLUDE_MODULE: &str = r#"#[allow(dead_code, unused_imports)]
+mod futhark {
+    include!(concat!(env!("OUT_DIR"), "/futhark/futhark_lib.rs"));
+}
+"#;
+
+const GENERATOR_CALL: &str = r#"
+    cargo_futhark::Generator::new("src/lib.fut")
+        .with_target_if(cargo_futhark::Target::C, cfg!(feature = "c"))
+        .with_target_if(cargo_futhark::Target::MultiCore, cfg!(feature = "multicore"))
+        .with_target_if(cargo_futhark::Target::OpenCL, cfg!(feature = "opencl"))
+        .with_target_if(cargo_futhark::Target::Cuda, cfg!(feature = "cuda"))
+        .with_target_if(cargo_futhark::Target::ISPC, cfg!(feature = "ispc"))
+        .ndarray(cfg!(feature = "ndarray"))
+        .serde(cfg!(feature = "serde"))
+        .test_backend(cfg!(feature = "test-backend"))
+        .run()
+        .expect("Failed to build Futhark code.");
+"#;
+
+pub fn init_project(path: &Path) -> Result<()> {
+    let cargo_toml_path = path.join("Cargo.toml");
+    ensure!(
+        cargo_toml_path.is_file(),
+        "There is no Cargo.toml file in '{}', use `cargo futhark new` to create a new project.",
+        path.display()
+    );
+
+    let mut manifest = fs::read_to_string(&cargo_toml_path)
+        .wrap_err("Failed to read Cargo.toml file.")?
+        .parse::<DocumentMut>()
+        .wrap_err("Failed to parse Cargo.toml file.")?;
+
+    let name = manifest
+        .get("package")
+        .ok_or_else(|| {
+            eyre!("Cargo.toml has no [package], run `cargo futhark init` in a package instead.")
+        })?
+        .get("name")
+        .and_then(Item::as_str)
+        .ok_or_else(|| eyre!("Cargo.toml does not contain a package name."))?;
+    let variables = Variables::new(name, Backend::C);
+
+    // The build script is the most likely to fail, so it goes before Cargo.toml is changed.
+    let build_script = project::build_script(&manifest)?;
+    add_build_script(&path.join(build_script), &variables)
+        .wrap_err("Failed to add build script.")?;
+
+    merge_manifest(&mut manifest, &variables).wrap_err("Failed to update Cargo.toml file.")?;
+    fs::write(&cargo_toml_path, manifest.to_string())
+        .wrap_err("Failed to write Cargo.toml file.")?;
+    println!("Updated Cargo.toml.");
+    add_futhark_source(path, &variables).wrap_err("Failed to add Futhark source file.")?;
+    add_include(path, &variables).wrap_err("Failed to include the generated bindings.")?;
+
+    println!();
+    println!("Futhark was added to '{}' successfully.", path.display());
+
+    Ok(())
+}
+
+fn template_file(path: &str, variables: &Variables) -> Result<String> {
+    let content = LIB_TEMPLATE
+        .get_file(path)
+        .and_then(|file| file.contents_utf8())
+        .ok_or_else(|| eyre!("The template does not contain {path}."))?;
+
+    variables
+        .render(content)
+        .wrap_err_with(|| format!("Failed to render {path}."))
+}
+
+/// Adds the features and dependencies of the project template,
+/// but keeps everything that is already present.
+fn merge_manifest(manifest: &mut DocumentMut, variables: &Variables) -> Result<()> {
+    let template = template_file("Cargo.toml.in", variables)?
+        .parse::<DocumentMut>()
+        .wrap_err("Failed to parse template Cargo.toml file.")?;
+
+    for table_name in ["features", "dependencies", "build-dependencies"] {
+        let Some(template_table) = template.get(table_name).and_then(Item::as_table) else {
+            continue;
+        };
+
+        let table = manifest
+            .entry(table_name)
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .ok_or_else(|| eyre!("[{table_name}] is not a table."))?;
+
+        for (key, value) in template_table.iter() {
+            if !table.contains_key(key) {
+                table.insert(key, value.clone());
+            }
+        }
+    }
+
+    let default = manifest["features"]["default"]
+        .as_array_mut()
+        .ok_or_else(|| eyre!("The default feature is not an array."))?;
+    let has_backend = default.iter().any(|feature| {
+        matches!(feature.as_str(), Some(feature) if Backend::value_variants()
+            .iter()
+            .any(|backend| backend.feature() == feature))
+    });
+    if !has_backend {
+        default.push(Backend::C.feature());
+    }
+
+    Ok(())
+}
+
+fn add_build_script(path: &Path, variables: &Variables) -> Result<()> {
+    if !path.exists() {
+        fs::write(path, template_file("build.rs", variables)?)
+            .wrap_err("Failed to write build script.")?;
+        println!("Created {}.", path.display());
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(path).wrap_err("Failed to read build script.")?;
+    if content.contains("cargo_futhark") {
+        println!("{} already uses cargo-futhark.", path.display());
+        return Ok(());
+    }
+
+    let Some(body_start) = main_body_start(&content) else {
+        bail!(
+            "Could not find `fn main()` in {}, add this to its main function:\n{GENERATOR_CALL}",
+            path.display()
+        );
+    };
+
+    let mut new_content = content;
+    new_content.insert_str(body_start, GENERATOR_CALL);
+    fs::write(path, new_content).wrap_err("Failed to write build script.")?;
+    println!("Added the Futhark generator to {}.", path.display());
+
+    Ok(())
+}
+
+/// Returns the position right after the opening brace of `fn main`, ignoring line comments.
+fn main_body_start(content: &str) -> Option<usize> {
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let code = line.split("//").next().unwrap_or_default();
+
+        if let Some(main) = code.find("fn main") {
+            let rest = content[offset + main + "fn main".len()..].trim_start();
+            if rest.starts_with('(') {
+                let params = content.len() - rest.len();
+                return content[params..].find('{').map(|brace| params + brace + 1);
+            }
+        }
+
+        offset += line.len();
+    }
+
+    None
+}
+
+fn add_futhark_source(path: &Path, variables: &Variables) -> Result<()> {
+    let lib_fut = path.join("src").join("lib.fut");
+    if lib_fut.exists() {
+        println!("{} already exists.", lib_fut.display());
+        return Ok(());
+    }
+
+    fs::create_dir_all(path.join("src")).wrap_err("Failed to create src directory.")?;
+    fs::write(&lib_fut, template_file("src/lib.fut", variables)?)
+        .wrap_err("Failed to write Futhark source file.")?;
+    println!("Created {}.", lib_fut.display());
+
+    Ok(())
+}
+
+fn add_include(path: &Path, variables: &Variables) -> Result<()> {
+    let lib_rs = path.join("src").join("lib.rs");
+    let main_rs = path.join("src").join("main.rs");
+
+    let (crate_root, include) = if lib_rs.exists() || !main_rs.exists() {
+        (lib_rs, format!("{INCLUDE_LINE}\n"))
+    } else {
+        (main_rs, INCLUDE_MODULE.to_string())
+    };
+
+    if !crate_root.exists() {
+        fs::write(&crate_root, template_file("src/lib.rs", variables)?)
+            .wrap_err("Failed to write crate root.")?;
+        println!("Created {}.", crate_root.display());
+        return Ok(());
+    }
+
+    let mut content = fs::read_to_string(&crate_root).wrap_err("Failed to read crate root.")?;
+    if content.contains(INCLUDE_LINE) {
+        println!("{} already includes the bindings.", crate_root.display());
+        return Ok(());
+    }
+
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push('\n');
+    content.push_str(&include);
+
+    fs::write(&crate_root, content).wrap_err("Failed to write crate root.")?;
+    println!("Included the bindings in {}.", crate_root.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_main_body() {
+        let content = "fn main() {\n}\n";
+        assert_eq!(main_body_start(content), Some("fn main() {".len()));
+
+        let content = "// fn main() {}\nfn main () -> Result<(), ()> {\n    Ok(())\n}\n";
+        let start = main_body_start(content).unwrap();
+        assert!(content[..start].ends_with("Result<(), ()> {"));
+    }
+
+    #[test]
+    fn requires_main() {
+        assert_eq!(main_body_start("fn mainly() {}\n"), None);
+        assert_eq!(main_body_start("// fn main() {}\n"), None);
+        assert_eq!(main_body_start(""), None);
+    }
+}
@@ -0,0 +1,16 @@
+use include_dir::{include_dir, Dir};
+
+pub mod bench;
+pub mod bindings;
+pub mod check;
+pub mod clean;
+pub mod doctor;
+pub mod init;
+pub mod new;
+pub mod project;
+pub mod template;
+pub mod test;
+
+pub static LIB_TEMPLATE: Dir = include_dir!("$CARGO_MANIFEST_DIR/templates/lib");
+pub static BIN_TEMPLATE: Dir = include_dir!("$CARGO_MANIFEST_DIR/templates/bin");
+pub static WORKSPACE_TEMPLATE: Dir = include_dir!("$CARGO_MANIFEST_DIR/templates/workspace");
@@ -0,0 +1,187 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{ensure, eyre, Context, Result};
+use toml_edit::{Array, DocumentMut};
+
+use clap::ValueEnum;
+
+use crate::commands::{
+    project,
+    template::{self, Backend, Variables},
+    BIN_TEMPLATE, LIB_TEMPLATE, WORKSPACE_TEMPLATE,
+};
+
+/// Project layouts supported by `cargo futhark new`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Template {
+    /// A library exposing the Futhark entry points.
+    Lib,
+    /// A binary calling the Futhark entry points directly.
+    Bin,
+    /// A workspace with a library like `lib` and a binary using it.
+    Workspace,
+}
+
+pub fn new_project(name: &str, template: Template, backend: Backend, register: bool) -> Result<()> {
+    template::validate_crate_name(name)?;
+
+    let project = Path::new(name);
+
+    ensure!(
+        !project.exists(),
+        "A directory with the name '{name}' already exists."
+    );
+
+    fs::create_dir(project).wrap_err("Failed to create project directory.")?;
+
+    let variables = Variables::new(name, backend);
+    match template {
+        Template::Lib => template::extract(&LIB_TEMPLATE, project, &variables)?,
+        Template::Bin => template::extract(&BIN_TEMPLATE, project, &variables)?,
+        Template::Workspace => {
+            template::extract(&WORKSPACE_TEMPLATE, project, &variables)?;
+
+            let lib = project.join("lib");
+            let lib_variables = variables.with_crate_name(&format!("{name}-lib"));
+            fs::create_dir(&lib).wrap_err("Failed to create library directory.")?;
+            template::extract(&LIB_TEMPLATE, &lib, &lib_variables)?;
+        }
+    }
+
+    println!("The new project '{name}' was created successfully.");
+
+    let Some(parent_manifest) = project::nearest_manifest() else {
+        return Ok(());
+    };
+
+    if template == Template::Workspace {
+        println!();
+        println!(
+            "Cargo does not support nested workspaces, so it was not added to {}.",
+            parent_manifest.display()
+        );
+    } else if !register {
+        println!();
+        println!("You might want to add it to your existing Cargo.toml file:");
+        println!("[workspace]");
+        println!("members = [\"{name}\"]");
+    } else {
+        match workspace_manifest(&parent_manifest) {
+            Ok(Some(workspace_manifest)) => register_member(&workspace_manifest, project)
+                .wrap_err("Failed to add the project to the workspace.")?,
+            Ok(None) => {
+                println!();
+                println!(
+                    "{} is not part of a workspace, so the project was not added to one.",
+                    parent_manifest.display()
+                );
+            }
+            Err(err) => {
+                println!();
+                println!(
+                    "Could not find the workspace of {}, so the project was not added to one: {err:#}",
+                    parent_manifest.display()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the manifest of the workspace that `manifest_path` belongs to.
+///
+/// For packages within a workspace, the root is taken from `cargo metadata`.
+/// Plain packages outside of any workspace return `None`.
+fn workspace_manifest(manifest_path: &Path) -> Result<Option<PathBuf>> {
+    if has_workspace(manifest_path)? {
+        return Ok(Some(manifest_path.to_path_buf()));
+    }
+
+    let metadata = project::metadata(manifest_path)?;
+    let root = metadata["workspace_root"]
+        .as_str()
+        .map(|root| Path::new(root).join("Cargo.toml"))
+        .ok_or_else(|| eyre!("`cargo metadata` did not report a workspace root."))?;
+
+    Ok(has_workspace(&root)?.then_some(root))
+}
+
+fn has_workspace(manifest_path: &Path) -> Result<bool> {
+    let manifest = fs::read_to_string(manifest_path)
+        .wrap_err_with(|| format!("Failed to read {}.", manifest_path.display()))?
+        .parse::<DocumentMut>()
+        .wrap_err_with(|| format!("Failed to parse {}.", manifest_path.display()))?;
+
+    Ok(manifest.contains_key("workspace"))
+}
+
+/// Adds `project` to the workspace members of `manifest_path`.
+///
+/// The manifest must already contain a `[workspace]` table.
+fn register_member(manifest_path: &Path, project: &Path) -> Result<()> {
+    let mut manifest = fs::read_to_string(manifest_path)
+        .wrap_err("Failed to read parent Cargo.toml file.")?
+        .parse::<DocumentMut>()
+        .wrap_err("Failed to parse parent Cargo.toml file.")?;
+
+    let root = manifest_path
+        .parent()
+        .unwrap()
+        .canonicalize()
+        .wrap_err("Failed to resolve workspace directory.")?;
+    let member = project
+        .canonicalize()
+        .wrap_err("Failed to resolve project directory.")?
+        .strip_prefix(&root)
+        .wrap_err("Project is not inside the workspace.")?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let workspace = manifest
+        .get_mut("workspace")
+        .ok_or_else(|| eyre!("{} has no [workspace].", manifest_path.display()))?
+        .as_table_like_mut()
+        .ok_or_else(|| eyre!("[workspace] is not a table."))?;
+    let members = workspace
+        .entry("members")
+        .or_insert(toml_edit::value(Array::new()))
+        .as_array_mut()
+        .ok_or_else(|| eyre!("workspace.members is not an array."))?;
+
+    if members
+        .iter()
+        .any(|pattern| matches!(pattern.as_str(), Some(pattern) if covers(pattern, &member)))
+    {
+        println!("The project is already a member of the workspace.");
+        return Ok(());
+    }
+
+    members.push(member.as_str());
+    fs::write(manifest_path, manifest.to_string())
+        .wrap_err("Failed to write parent Cargo.toml file.")?;
+
+    println!(
+        "Added '{member}' to the workspace members in {}.",
+        manifest_path.display()
+    );
+
+    Ok(())
+}
+
+/// Checks whether a workspace member pattern includes `member`.
+///
+/// Only trailing `*` wildcards like `crates/*` are supported.
+fn covers(pattern: &str, member: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/');
+
+    match pattern.strip_suffix('*') {
+        Some(prefix) => member.starts_with(prefix) && !member[prefix.len()..].contains('/'),
+        None => pattern == member,
+    }
+}
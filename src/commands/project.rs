@@ -0,0 +1,135 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use eyre::{bail, ensure, eyre, Context, Result};
+use toml_edit::{DocumentMut, Item, Value};
+
+/// A Cargo package using cargo-futhark.
+pub struct Project {
+    /// Directory containing the `Cargo.toml` file.
+    pub root: PathBuf,
+    /// The Futhark file containing the entry points.
+    pub source: PathBuf,
+}
+
+impl Project {
+    /// Loads the package of `manifest_path` or of the nearest `Cargo.toml` file.
+    ///
+    /// The Futhark source is taken from `package.metadata.futhark.source`
+    /// or from the `Generator::new` call in the build script.
+    pub fn find(manifest_path: Option<&Path>) -> Result<Self> {
+        let manifest_path = match manifest_path {
+            Some(path) => path.to_path_buf(),
+            None => nearest_manifest().ok_or_else(|| {
+                eyre!("Could not find Cargo.toml in the current directory or its parents.")
+            })?,
+        };
+        let root = match manifest_path.parent() {
+            Some(parent) if parent.as_os_str().is_empty() => PathBuf::from("."),
+            Some(parent) => parent.to_path_buf(),
+            None => bail!("Invalid manifest path '{}'.", manifest_path.display()),
+        };
+
+        let manifest = fs::read_to_string(&manifest_path)
+            .wrap_err_with(|| format!("Failed to read {}.", manifest_path.display()))?
+            .parse::<DocumentMut>()
+            .wrap_err_with(|| format!("Failed to parse {}.", manifest_path.display()))?;
+
+        let configured_source = manifest
+            .get("package")
+            .and_then(|package| package.get("metadata"))
+            .and_then(|metadata| metadata.get("futhark"))
+            .and_then(|futhark| futhark.get("source"))
+            .and_then(Item::as_str);
+
+        let source = match configured_source {
+            Some(source) => source.to_string(),
+            None => {
+                let build_script = root.join(build_script(&manifest)?);
+                generator_source(&build_script).ok_or_else(|| {
+                    eyre!(
+                        "Could not find `Generator::new` in {}, \
+                         set `package.metadata.futhark.source` in Cargo.toml instead.",
+                        build_script.display()
+                    )
+                })?
+            }
+        };
+
+        Ok(Project {
+            source: root.join(source),
+            root,
+        })
+    }
+
+    /// Path of the package manifest.
+    pub fn manifest_path(&self) -> PathBuf {
+        self.root.join("Cargo.toml")
+    }
+}
+
+/// Returns the build script of the package, honoring `package.build`.
+pub fn build_script(manifest: &DocumentMut) -> Result<String> {
+    let build_script = match manifest.get("package").map(|package| package.get("build")) {
+        None => bail!("Cargo.toml does not contain a [package] section."),
+        Some(None) => "build.rs".to_string(),
+        Some(Some(build)) => match build.as_value() {
+            Some(Value::String(build)) => build.value().clone(),
+            Some(Value::Boolean(build)) if *build.value() => "build.rs".to_string(),
+            _ => bail!("The build script is disabled in Cargo.toml."),
+        },
+    };
+
+    Ok(build_script)
+}
+
+/// Extracts the string literal passed to `Generator::new` in `build_script`.
+fn generator_source(build_script: &Path) -> Option<String> {
+    let content = fs::read_to_string(build_script).ok()?;
+    let call = content.find("Generator::new(")? + "Generator::new(".len();
+    let literal = content[call..].trim_start().strip_prefix('"')?;
+    let end = literal.find('"')?;
+
+    Some(literal[..end].to_string())
+}
+
+/// Returns the nearest `Cargo.toml` in the current directory or its ancestors.
+pub fn nearest_manifest() -> Option<PathBuf> {
+    let absolute_path = Path::new(".").canonicalize().ok()?;
+
+    absolute_path
+        .ancestors()
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|manifest| manifest.is_file())
+}
+
+/// Runs `cargo metadata` without dependencies for `manifest_path`.
+pub fn metadata(manifest_path: &Path) -> Result<serde_json::Value> {
+    let output = cargo()
+        .args([
+            "metadata",
+            "--no-deps",
+            "--format-version",
+            "1",
+            "--manifest-path",
+        ])
+        .arg(manifest_path)
+        .output()
+        .wrap_err("Failed to run `cargo metadata`.")?;
+    ensure!(
+        output.status.success(),
+        "`cargo metadata` failed:\n{}",
+        String::from_utf8_lossy(&output.stderr).trim_end()
+    );
+
+    serde_json::from_slice(&output.stdout)
+        .wrap_err("Failed to parse the output of `cargo metadata`.")
+}
+
+/// Returns a command running the cargo executable that invoked us.
+pub fn cargo() -> Command {
+    Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+}
@@ -0,0 +1,256 @@
+use std::{fs, path::Path, process::Command};
+
+use cargo_futhark::{Target, RUST_KEYWORDS};
+use clap::ValueEnum;
+use eyre::{bail, ensure, Context, Result};
+use include_dir::{Dir, DirEntry};
+
+/// Edition of the generated projects.
+///
+/// The generated bindings do not compile with the 2024 edition yet.
+const EDITION: &str = "2021";
+
+/// Backends that can be enabled by default in new projects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    C,
+    #[value(name = "multicore")]
+    MultiCore,
+    #[value(name = "opencl")]
+    OpenCL,
+    Cuda,
+    Ispc,
+}
+
+impl Backend {
+    /// Name of the Cargo feature enabling the backend.
+    pub fn feature(self) -> &'static str {
+        match self {
+            Backend::C => "c",
+            Backend::MultiCore => "multicore",
+            Backend::OpenCL => "opencl",
+            Backend::Cuda => "cuda",
+            Backend::Ispc => "ispc",
+        }
+    }
+
+    /// Name of the backend type in the generated `backends` module.
+    pub fn struct_name(self) -> &'static str {
+        match self {
+            Backend::C => "C",
+            Backend::MultiCore => "MultiCore",
+            Backend::OpenCL => "OpenCL",
+            Backend::Cuda => "Cuda",
+            Backend::Ispc => "Ispc",
+        }
+    }
+
+    /// The Futhark target compiled for the backend.
+    pub fn target(self) -> Target {
+        match self {
+            Backend::C => Target::C,
+            Backend::MultiCore => Target::MultiCore,
+            Backend::OpenCL => Target::OpenCL,
+            Backend::Cuda => Target::Cuda,
+            Backend::Ispc => Target::ISPC,
+        }
+    }
+}
+
+/// Values of the `{{variable}}` placeholders in template files.
+pub struct Variables {
+    crate_name: String,
+    authors: Vec<String>,
+    backend: Backend,
+}
+
+impl Variables {
+    pub fn new(crate_name: &str, backend: Backend) -> Self {
+        Variables {
+            crate_name: crate_name.to_string(),
+            authors: git_author().into_iter().collect(),
+            backend,
+        }
+    }
+
+    /// Returns a copy for a crate with a different name.
+    pub fn with_crate_name(&self, crate_name: &str) -> Self {
+        Variables {
+            crate_name: crate_name.to_string(),
+            authors: self.authors.clone(),
+            backend: self.backend,
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "crate-name" => self.crate_name.clone(),
+            "crate_name" => self.crate_name.replace('-', "_"),
+            "authors" => toml_edit::Array::from_iter(&self.authors).to_string(),
+            "edition" => EDITION.to_string(),
+            "default-backend" => self.backend.feature().to_string(),
+            "backend-type" => self.backend.struct_name().to_string(),
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    /// Replaces all placeholders in `content`.
+    ///
+    /// Braces that do not enclose a variable name, like in `format!("{{}}")`,
+    /// are left untouched, but unknown variable names are an error.
+    pub fn render(&self, content: &str) -> Result<String> {
+        let mut rendered = String::with_capacity(content.len());
+        let mut rest = content;
+
+        while let Some(start) = rest.find("{{") {
+            let after_start = &rest[start + 2..];
+            let Some(end) = after_start.find("}}") else {
+                break;
+            };
+
+            let name = &after_start[..end];
+            let is_variable = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c == '-' || c == '_');
+
+            rendered.push_str(&rest[..start]);
+            if is_variable {
+                match self.get(name) {
+                    Some(value) => rendered.push_str(&value),
+                    None => bail!("Unknown template variable '{name}'."),
+                }
+                rest = &after_start[end + 2..];
+            } else {
+                rendered.push_str("{{");
+                rest = after_start;
+            }
+        }
+        rendered.push_str(rest);
+
+        Ok(rendered)
+    }
+}
+
+/// Returns `user.name <user.email>` from the git configuration.
+fn git_author() -> Option<String> {
+    let config = |key: &str| {
+        let output = Command::new("git").args(["config", key]).output().ok()?;
+        let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+        (output.status.success() && !value.is_empty()).then_some(value)
+    };
+
+    let name = config("user.name")?;
+    Some(match config("user.email") {
+        Some(email) => format!("{name} <{email}>"),
+        None => name,
+    })
+}
+
+/// Extracts `template` into `path`, renders every file
+/// and removes the `.in` suffix from `Cargo.toml.in` files.
+pub fn extract(template: &Dir, path: &Path, variables: &Variables) -> Result<()> {
+    template
+        .extract(path)
+        .wrap_err("Failed to extract template.")?;
+
+    for file in files(template) {
+        let file_path = path.join(file);
+        let content = fs::read_to_string(&file_path)
+            .wrap_err_with(|| format!("Failed to read {}.", file_path.display()))?;
+        let rendered = variables
+            .render(&content)
+            .wrap_err_with(|| format!("Failed to render {}.", file.display()))?;
+
+        let target_path = if file.ends_with("Cargo.toml.in") {
+            fs::remove_file(&file_path).wrap_err("Failed to remove Cargo.toml.in file.")?;
+            file_path.with_extension("")
+        } else {
+            file_path
+        };
+        fs::write(&target_path, rendered)
+            .wrap_err_with(|| format!("Failed to write {}.", target_path.display()))?;
+    }
+
+    Ok(())
+}
+
+fn files<'a>(dir: &'a Dir) -> Vec<&'a Path> {
+    dir.entries()
+        .iter()
+        .flat_map(|entry| match entry {
+            DirEntry::Dir(dir) => files(dir),
+            DirEntry::File(file) => vec![file.path()],
+        })
+        .collect()
+}
+
+/// Checks that `name` can be used as a package name, like `cargo new` does.
+pub fn validate_crate_name(name: &str) -> Result<()> {
+    ensure!(!name.is_empty(), "The package name must not be empty.");
+
+    if let Some(c) = name
+        .chars()
+        .find(|&c| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+    {
+        bail!(
+            "Invalid character '{c}' in package name '{name}', \
+             only ASCII letters, digits, '-' and '_' are allowed."
+        );
+    }
+
+    ensure!(
+        !name.starts_with(|c: char| c.is_ascii_digit()),
+        "The package name '{name}' must not start with a digit."
+    );
+
+    let lower_name = name.to_ascii_lowercase();
+    ensure!(
+        !RUST_KEYWORDS.contains(&name),
+        "The package name '{name}' is a Rust keyword."
+    );
+    ensure!(
+        !RESERVED_NAMES.contains(&lower_name.as_str()),
+        "The package name '{name}' is reserved."
+    );
+
+    Ok(())
+}
+
+/// Names of standard crates, Cargo build directories and Windows devices.
+const RESERVED_NAMES: &[&str] = &[
+    "alloc",
+    "core",
+    "proc_macro",
+    "proc-macro",
+    "std",
+    "test",
+    "build",
+    "deps",
+    "examples",
+    "incremental",
+    "con",
+    "prn",
+    "aux",
+    "nul",
+    "com1",
+    "com2",
+    "com3",
+    "com4",
+    "com5",
+    "com6",
+    "com7",
+    "com8",
+    "com9",
+    "lpt1",
+    "lpt2",
+    "lpt3",
+    "lpt4",
+    "lpt5",
+    "lpt6",
+    "lpt7",
+    "lpt8",
+    "lpt9",
+];
@@ -0,0 +1,64 @@
+use std::{path::Path, process::Command};
+
+use eyre::{ensure, Context, Result};
+
+use crate::commands::{
+    project::{self, Project},
+    template::Backend,
+};
+
+/// Runs the tests generated from the `-- ==` blocks of the Futhark source.
+///
+/// Without `backends`, the tests of all backends enabled by default are run.
+pub fn test(manifest_path: Option<&Path>, backends: &[Backend], args: &[String]) -> Result<()> {
+    let project = Project::find(manifest_path)?;
+
+    let mut cargo = project::cargo();
+    cargo
+        .arg("test")
+        .arg("--manifest-path")
+        .arg(project.manifest_path());
+    select_tests(&mut cargo, "futhark_tests", backends, args, &[]);
+
+    let status = cargo.status().wrap_err("Failed to run `cargo test`.")?;
+    ensure!(status.success(), "Some Futhark tests failed.");
+
+    Ok(())
+}
+
+/// Adds the arguments to run the generated tests in `module` for `backends`.
+///
+/// The generated tests live in `<module>::<backend>`, `args` are passed to `cargo test`
+/// and `test_args` to the test binaries.
+pub fn select_tests(
+    cargo: &mut Command,
+    module: &str,
+    backends: &[Backend],
+    args: &[String],
+    test_args: &[&str],
+) {
+    if !backends.is_empty() {
+        let features = backends
+            .iter()
+            .map(|backend| backend.feature())
+            .collect::<Vec<_>>()
+            .join(",");
+        cargo.arg("--features").arg(features);
+    }
+
+    cargo.args(args);
+    if !args.iter().any(|arg| arg == "--") {
+        cargo.arg("--");
+    }
+    cargo.args(test_args);
+
+    if backends.is_empty() {
+        cargo.arg(format!("{module}::"));
+    } else {
+        cargo.args(
+            backends
+                .iter()
+                .map(|backend| format!("{module}::{}::", backend.feature())),
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{manifest::Manifest, template, Target};
+use crate::{manifest::Manifest, source::Source, template, toolchain, Bindings, Target};
 use bindgen::callbacks::ParseCallbacks;
 use enumflags2::BitFlags;
 use eyre::{bail, ensure, Context, Result};
@@ -14,6 +14,7 @@ use std::{
 mod names {
     pub const TARGET_DIR: &str = "futhark";
     pub const RAW_TARGET_DIR: &str = "futhark_raw";
+    pub const TEST_DIR: &str = "test";
 
     pub const LIBRARY: &str = "futhark_lib";
     pub const MANIFEST: &str = "futhark_lib.json";
@@ -64,6 +65,7 @@ pub struct Generator {
     watch: bool,
     cuda_home: Option<PathBuf>,
     targets: BitFlags<Target>,
+    options: template::Options,
 }
 
 impl Generator {
@@ -73,15 +75,21 @@ impl Generator {
     ///
     /// The defaults are:
     /// - `watch_sources = true`
+    /// - `output_structs = false`
+    /// - `ndarray = false`
+    /// - `serde = false`
+    /// - `test_backend = false`
     /// - `targets = EMPTY`
     ///
-    /// You must add at least on [`Target`] before you call [`Generator::run`].
+    /// You must add at least on [`Target`] or enable [`Generator::test_backend`]
+    /// before you call [`Generator::run`].
     pub fn new(source: impl Into<PathBuf>) -> Self {
         Generator {
             source: source.into(),
             cuda_home: None,
             watch: true,
             targets: BitFlags::empty(),
+            options: template::Options::default(),
         }
     }
 
@@ -93,6 +101,62 @@ impl Generator {
         self
     }
 
+    /// Return output structs from entry points with more than one output.
+    ///
+    /// Entry points with more than one output return an anonymous tuple by default.
+    /// When enabled, they return a struct like `EntrySwapOutput` instead.
+    /// Futhark does not name the outputs of entry points, so fields of opaque types
+    /// are named after their type, like `stats`, and all others by position: `out_0`, `out_1`, ….
+    ///
+    /// Disabled by default.
+    pub fn output_structs(&mut self, enabled: bool) -> &mut Self {
+        self.options.output_structs = enabled;
+        self
+    }
+
+    /// Generate conversions between Futhark arrays and the [`ndarray`](https://docs.rs/ndarray) crate.
+    ///
+    /// Every array type gets a `from_ndarray` constructor and a `to_ndarray` method.
+    /// The crate including the generated code must depend on `ndarray`.
+    /// This is especially useful with an optional dependency and the [`cfg!`] macro.
+    ///
+    /// Disabled by default.
+    pub fn ndarray(&mut self, enabled: bool) -> &mut Self {
+        self.options.ndarray = enabled;
+        self
+    }
+
+    /// Generate [`serde`](https://docs.rs/serde) support for arrays and opaque values.
+    ///
+    /// Arrays implement `Serialize` as their `shape` and flat, row-major `data`.
+    /// Opaque values implement `Serialize` using their `to_bytes` representation.
+    /// Because both are bound to a `Context`, they are deserialized through a `Seed`
+    /// created with `seed(&context)`, which implements `DeserializeSeed`.
+    /// The `seed` of opaque values is unsafe, as Futhark can not validate their bytes.
+    /// The crate including the generated code must depend on `serde`.
+    ///
+    /// Disabled by default.
+    pub fn serde(&mut self, enabled: bool) -> &mut Self {
+        self.options.serde = enabled;
+        self
+    }
+
+    /// Generate the pure-Rust `backends::Test` backend.
+    ///
+    /// The test backend does not link any C code generated by Futhark.
+    /// Its entry points call closures registered on `Config<backends::Test>`
+    /// and arrays are kept in memory.
+    /// This allows testing code that uses a `Context` without a C toolchain.
+    ///
+    /// The Futhark compiler is still required to generate the manifest.
+    /// Without any other [`Target`], no C code is compiled.
+    ///
+    /// Disabled by default.
+    pub fn test_backend(&mut self, enabled: bool) -> &mut Self {
+        self.options.test_backend = enabled;
+        self
+    }
+
     /// Specify a custom CUDA home path.
     ///
     /// This will add the following:
@@ -129,8 +193,8 @@ impl Generator {
         ensure!(self.source.is_file(), "Futhark source file does not exist.");
 
         ensure!(
-            !self.targets.is_empty(),
-            "At least one target must be built."
+            !self.targets.is_empty() || self.options.test_backend,
+            "At least one target or the test backend must be built."
         );
 
         self.build_targets().wrap_err("Failed to build targets.")?;
@@ -140,23 +204,55 @@ impl Generator {
 
         Ok(())
     }
+
+    /// Generates the safe wrapper without building the targets.
+    ///
+    /// Only the Futhark compiler is run, using the `c` target to obtain the manifest.
+    /// Its output is placed in `out_dir`.
+    /// This is used by `cargo futhark bindings` to inspect the generated code.
+    pub fn bindings(&self, out_dir: impl AsRef<Path>) -> Result<Bindings> {
+        ensure!(self.source.is_file(), "Futhark source file does not exist.");
+
+        let out_dir = out_dir.as_ref();
+        fs::create_dir_all(out_dir).wrap_err("Could not create output dir.")?;
+
+        let futhark_output = self
+            .futhark_command(Target::C, out_dir)
+            .output()
+            .wrap_err("Failed to run Futhark compiler, is it installed?")?;
+        let diagnostics = String::from_utf8_lossy(&futhark_output.stderr).into_owned();
+
+        if !futhark_output.status.success() {
+            bail!(
+                "Failed to compile Futhark code:\n{}",
+                diagnostics.trim_end()
+            );
+        }
+
+        let manifest = self.load_manifest(&out_dir.join(names::MANIFEST))?;
+
+        Ok(Bindings {
+            manifest,
+            targets: self.targets,
+            options: self.options,
+            diagnostics,
+        })
+    }
 }
 
 impl Generator {
     fn generate_library(&mut self) -> Result<(), eyre::ErrReport> {
-        let any_target = self.targets.iter().next().unwrap();
+        let manifest_dir = match self.targets.iter().next() {
+            Some(target) => target.name(),
+            None => names::TEST_DIR,
+        };
         let manifest_path = cargo_out_dir()?
             .join(names::TARGET_DIR)
-            .join(any_target.name())
+            .join(manifest_dir)
             .join(names::MANIFEST);
-        let manifest = Manifest::from_json_file(&manifest_path).wrap_err_with(|| {
-            format!(
-                "Failed to load manifest file at {}.",
-                manifest_path.display()
-            )
-        })?;
+        let manifest = self.load_manifest(&manifest_path)?;
 
-        let rust_lib = template::combined(&manifest, self.targets).to_string();
+        let rust_lib = template::combined(&manifest, self.targets, &self.options).to_string();
         let rust_lib_path = cargo_out_dir()?
             .join(names::TARGET_DIR)
             .join(names::RS_FILE);
@@ -180,11 +276,32 @@ impl Generator {
         Ok(())
     }
 
+    /// Loads the manifest written by the Futhark compiler and adds the docs and tests of the source.
+    fn load_manifest(&self, manifest_path: &Path) -> Result<Manifest> {
+        let mut manifest = Manifest::from_json_file(manifest_path).wrap_err_with(|| {
+            format!(
+                "Failed to load manifest file at {}.",
+                manifest_path.display()
+            )
+        })?;
+
+        let source = Source::from_file(&self.source)?;
+        manifest.attach_docs(&source.doc_comments());
+        manifest.attach_tests(&source.test_blocks());
+
+        Ok(manifest)
+    }
+
     fn build_targets(&self) -> Result<()> {
         if self.watch {
             watch_source(&self.source).wrap_err("Failed to watch source files for changes.")?;
         }
 
+        if self.targets.is_empty() {
+            self.build_manifest()
+                .wrap_err("Failed to build manifest for the test backend.")?;
+        }
+
         if self.targets.contains(Target::C) {
             self.build_target(Target::C)
                 .wrap_err("Failed to build C target.")?;
@@ -220,31 +337,45 @@ impl Generator {
         Ok(())
     }
 
-    fn build_target(&self, target: Target) -> Result<()> {
+    /// Compile the Futhark code with the C backend only to obtain its manifest.
+    fn build_manifest(&self) -> Result<()> {
         let out_dir = cargo_out_dir()?;
-        let target_dir = out_dir.join(names::TARGET_DIR).join(target.name());
-        fs::create_dir_all(&target_dir).wrap_err("Could not create target dir.")?;
+        let target_dir = out_dir.join(names::TARGET_DIR).join(names::TEST_DIR);
+        let raw_target_dir = out_dir.join(names::RAW_TARGET_DIR).join(names::TEST_DIR);
 
-        let raw_target_dir = out_dir.join(names::RAW_TARGET_DIR).join(target.name());
-        fs::create_dir_all(&raw_target_dir).wrap_err("Could not create raw target dir.")?;
+        self.compile_futhark(Target::C, &target_dir, &raw_target_dir)
+    }
 
-        let futhark_status = Command::new("futhark")
-            .args([target.name(), "--library", "-o"])
-            .arg(raw_target_dir.join(names::LIBRARY))
-            .arg(self.source.as_os_str())
-            .status()
-            .wrap_err("Failed to run Futhark compiler.")?
-            .success();
+    fn build_target(&self, target: Target) -> Result<()> {
+        self.try_build_target(target)
+            .map_err(|err| self.explain_failure(target, err))
+    }
 
-        if !futhark_status {
-            bail!("Failed to compile Futhark code.");
+    /// Adds the dependencies of `target` that could not be found to `err`.
+    fn explain_failure(&self, target: Target, err: eyre::Report) -> eyre::Report {
+        let missing = toolchain::missing([target], self.cuda_home.as_deref());
+        if missing.is_empty() {
+            return err;
         }
 
-        fs::copy(
-            raw_target_dir.join(names::MANIFEST),
-            target_dir.join(names::MANIFEST),
-        )
-        .wrap_err("Failed to copy manifest file")?;
+        let list = missing
+            .iter()
+            .map(|requirement| format!("- {}: {}", requirement.name, requirement.hint))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        err.wrap_err(format!(
+            "These dependencies of the {target} target could not be found:\n{list}\n\
+             Run `cargo futhark doctor --backend {target}` for details."
+        ))
+    }
+
+    fn try_build_target(&self, target: Target) -> Result<()> {
+        let out_dir = cargo_out_dir()?;
+        let target_dir = out_dir.join(names::TARGET_DIR).join(target.name());
+        let raw_target_dir = out_dir.join(names::RAW_TARGET_DIR).join(target.name());
+
+        self.compile_futhark(target, &target_dir, &raw_target_dir)?;
 
         let prefix = format!("futhark_{target}_");
 
@@ -294,6 +425,60 @@ impl Generator {
 
         Ok(())
     }
+
+    fn compile_futhark(
+        &self,
+        target: Target,
+        target_dir: &Path,
+        raw_target_dir: &Path,
+    ) -> Result<()> {
+        fs::create_dir_all(target_dir).wrap_err("Could not create target dir.")?;
+        fs::create_dir_all(raw_target_dir).wrap_err("Could not create raw target dir.")?;
+
+        let futhark_output = self
+            .futhark_command(target, raw_target_dir)
+            .output()
+            .wrap_err("Failed to run Futhark compiler, is it installed?")?;
+
+        // Every target reports the same warnings, so only show them once.
+        let first_target = self.targets.iter().next().unwrap_or(target) == target;
+        let diagnostics = String::from_utf8_lossy(&futhark_output.stderr);
+        if first_target || !futhark_output.status.success() {
+            emit_warnings(&diagnostics);
+        }
+
+        if !futhark_output.status.success() {
+            bail!(
+                "Failed to compile Futhark code:\n{}",
+                diagnostics.trim_end()
+            );
+        }
+
+        fs::copy(
+            raw_target_dir.join(names::MANIFEST),
+            target_dir.join(names::MANIFEST),
+        )
+        .wrap_err("Failed to copy manifest file")?;
+
+        Ok(())
+    }
+
+    fn futhark_command(&self, target: Target, raw_target_dir: &Path) -> Command {
+        let mut command = Command::new("futhark");
+        command
+            .args([target.name(), "--library", "-o"])
+            .arg(raw_target_dir.join(names::LIBRARY))
+            .arg(self.source.as_os_str());
+
+        command
+    }
+}
+
+/// Forwards compiler diagnostics to cargo, which shows them even if the build succeeds.
+fn emit_warnings(diagnostics: &str) {
+    for line in diagnostics.lines().filter(|line| !line.trim().is_empty()) {
+        println!("cargo:warning={line}");
+    }
 }
 
 fn watch_source(source: &Path) -> Result<()> {
@@ -22,6 +22,10 @@
 //!
 
 mod manifest;
+/// Shared with the `cargo futhark` binary, not part of the public API.
+#[doc(hidden)]
+pub use manifest::RUST_KEYWORDS;
+mod source;
 mod template;
 
 mod target;
@@ -30,4 +34,9 @@ pub use target::Target;
 mod generator;
 pub use generator::Generator;
 
+mod bindings;
+pub use bindings::Bindings;
+
+pub mod toolchain;
+
 pub use eyre::Result;
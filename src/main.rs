@@ -1,10 +1,9 @@
-use std::{fs, path::Path};
+use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
-use eyre::{ensure, Context, Result};
-use include_dir::{include_dir, Dir};
+use eyre::Result;
 
-static PROJECT_TEMPLATE: Dir = include_dir!("$CARGO_MANIFEST_DIR/project-template");
+mod commands;
 
 #[derive(Parser)]
 #[command(name = "cargo")]
@@ -23,7 +22,117 @@ enum Cli {
 #[derive(Subcommand)]
 enum Commands {
     #[command(about = "Create a new Cargo-Futhark project")]
-    New { name: String },
+    New {
+        name: String,
+        /// Layout of the new project.
+        #[arg(long, value_enum, default_value_t = commands::new::Template::Lib)]
+        template: commands::new::Template,
+        /// Backend enabled by the default feature.
+        #[arg(long, value_enum, default_value_t = commands::template::Backend::C)]
+        backend: commands::template::Backend,
+        /// Do not add the project to the workspace of the parent Cargo.toml.
+        #[arg(long)]
+        no_workspace: bool,
+    },
+    #[command(about = "Add Futhark to an existing Cargo package")]
+    Init {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    #[command(about = "Check the Futhark code for errors")]
+    Check {
+        /// Path to Cargo.toml, defaults to the nearest one.
+        #[arg(long)]
+        manifest_path: Option<PathBuf>,
+    },
+    #[command(about = "Check the Futhark code and run `cargo build`")]
+    Build {
+        /// Path to Cargo.toml, defaults to the nearest one.
+        #[arg(long)]
+        manifest_path: Option<PathBuf>,
+        /// Arguments passed on to `cargo build`.
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    #[command(about = "Run the Futhark tests through the generated bindings")]
+    Test {
+        /// Path to Cargo.toml, defaults to the nearest one.
+        #[arg(long)]
+        manifest_path: Option<PathBuf>,
+        /// Backend to test, can be repeated. Defaults to the enabled backends.
+        #[arg(long = "backend", value_enum)]
+        backends: Vec<commands::template::Backend>,
+        /// Arguments passed on to `cargo test`.
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    #[command(about = "Benchmark the Futhark entry points through the generated bindings")]
+    Bench {
+        /// Path to Cargo.toml, defaults to the nearest one.
+        #[arg(long)]
+        manifest_path: Option<PathBuf>,
+        /// Backend to benchmark, can be repeated. Defaults to the enabled backends.
+        #[arg(long = "backend", value_enum)]
+        backends: Vec<commands::template::Backend>,
+        /// Number of measured runs per dataset.
+        #[arg(long, default_value_t = 10)]
+        runs: usize,
+        /// Number of runs before measuring.
+        #[arg(long, default_value_t = 1)]
+        warmup: usize,
+        /// Where to save the JSON report, defaults to `target/futhark-bench/<commit>.json`.
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Report of an earlier run to compare the results with.
+        #[arg(long)]
+        compare: Option<PathBuf>,
+        /// Arguments passed on to `cargo test`.
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    #[command(about = "Print the bindings generated for the Futhark code")]
+    Bindings {
+        /// Futhark source file, defaults to the one of the project.
+        source: Option<PathBuf>,
+        /// Path to Cargo.toml, defaults to the nearest one.
+        #[arg(long)]
+        manifest_path: Option<PathBuf>,
+        /// Backend to generate bindings for, can be repeated. Defaults to `c`.
+        #[arg(long = "backend", value_enum)]
+        backends: Vec<commands::template::Backend>,
+        #[command(flatten)]
+        options: commands::bindings::CodeOptions,
+        /// Only print the code of this entry point or type.
+        #[arg(long, conflicts_with = "manifest")]
+        item: Option<String>,
+        /// Print a summary of the entry points and types instead of the code.
+        #[arg(long)]
+        manifest: bool,
+        /// File to write to instead of printing.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    #[command(about = "Remove the Futhark build artifacts without a full `cargo clean`")]
+    Clean {
+        /// Path to Cargo.toml, defaults to the nearest one.
+        #[arg(long)]
+        manifest_path: Option<PathBuf>,
+        /// Futhark cache file to remove, can be repeated.
+        #[arg(long = "cache-file")]
+        cache_files: Vec<PathBuf>,
+        /// Only print what would be removed.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    #[command(about = "Check that the tools and libraries for each backend are installed")]
+    Doctor {
+        /// Backend that must be usable, can be repeated. Defaults to reporting on all backends.
+        #[arg(long = "backend", value_enum)]
+        backends: Vec<commands::template::Backend>,
+        /// CUDA SDK to check first, like `Generator::with_cuda_home` in the build script.
+        #[arg(long)]
+        cuda_home: Option<PathBuf>,
+    },
 }
 
 fn main() -> Result<()> {
@@ -31,74 +140,96 @@ fn main() -> Result<()> {
 
     match &cli {
         Cli::Futhark {
-            command: Commands::New { name },
-        } => new_project(name),
-    }
-}
-
-fn new_project(name: &str) -> Result<()> {
-    let project = Path::new(name);
-
-    ensure!(
-        !project.exists(),
-        "A directory with the name '{name}' already exists."
-    );
-
-    fs::create_dir(project).wrap_err("Failed to create project directory.")?;
-
-    PROJECT_TEMPLATE
-        .extract(project)
-        .wrap_err("Failed to extract template.")?;
-
-    let cargo_toml_in_path = project.join("Cargo.toml.in");
-    let cargo_toml_path = project.join("Cargo.toml");
-    fs::rename(&cargo_toml_in_path, &cargo_toml_path)
-        .wrap_err("Failed to rename Cargo.toml file.")?;
-    let replacements = replace_in_file(cargo_toml_path, "{{lib-name}}", name)
-        .wrap_err("Failed to replace project name in Cargo.toml file.")?;
-    ensure!(
-        replacements == 1,
-        "Failed to replace project name in Cargo.toml file."
-    );
-
-    println!("The new project '{name}' was created successfully.");
-
-    if is_subproject() {
-        println!();
-        println!("You might want to add it to your existing Cargo.toml file:");
-        println!("[workspace]");
-        println!("members = [\"{name}\"]");
-    }
-
-    Ok(())
-}
-
-fn replace_in_file(path: impl AsRef<Path>, from: &str, to: &str) -> Result<usize> {
-    let content = fs::read_to_string(&path).wrap_err("Failed to read source file.")?;
-    let occurrences = content.match_indices(from).count();
-    let new_content = content.replace(from, to);
-    fs::write(&path, new_content).wrap_err("Failed to write after replacement.")?;
-
-    Ok(occurrences)
-}
-
-fn is_subproject() -> bool {
-    let absolute_path = match Path::new(".").canonicalize() {
-        Ok(path) => path,
-        Err(_) => return false,
-    };
-
-    let mut dir = absolute_path.as_path();
-
-    loop {
-        if dir.join("Cargo.toml").is_file() {
-            return true;
-        }
-
-        if dir.parent().is_none() {
-            return false;
+            command:
+                Commands::New {
+                    name,
+                    template,
+                    backend,
+                    no_workspace,
+                },
+        } => commands::new::new_project(name, *template, *backend, !no_workspace),
+        Cli::Futhark {
+            command: Commands::Init { path },
+        } => commands::init::init_project(path),
+        Cli::Futhark {
+            command: Commands::Check { manifest_path },
+        } => commands::check::check(manifest_path.as_deref()),
+        Cli::Futhark {
+            command:
+                Commands::Build {
+                    manifest_path,
+                    args,
+                },
+        } => commands::check::build(manifest_path.as_deref(), args),
+        Cli::Futhark {
+            command:
+                Commands::Test {
+                    manifest_path,
+                    backends,
+                    args,
+                },
+        } => commands::test::test(manifest_path.as_deref(), backends, args),
+        Cli::Futhark {
+            command:
+                Commands::Bench {
+                    manifest_path,
+                    backends,
+                    runs,
+                    warmup,
+                    output,
+                    compare,
+                    args,
+                },
+        } => commands::bench::bench(
+            manifest_path.as_deref(),
+            backends,
+            *runs,
+            *warmup,
+            output.as_deref(),
+            compare.as_deref(),
+            args,
+        ),
+        Cli::Futhark {
+            command:
+                Commands::Bindings {
+                    source,
+                    manifest_path,
+                    backends,
+                    options,
+                    item,
+                    manifest,
+                    output,
+                },
+        } => {
+            let show = match (item, manifest) {
+                (Some(item), _) => commands::bindings::Show::Item(item),
+                (None, true) => commands::bindings::Show::Manifest,
+                (None, false) => commands::bindings::Show::Code,
+            };
+
+            commands::bindings::bindings(
+                source.as_deref(),
+                manifest_path.as_deref(),
+                backends,
+                *options,
+                show,
+                output.as_deref(),
+            )
         }
-
-        dir = dir.parent().unwrap();
+        Cli::Futhark {
+            command:
+                Commands::Clean {
+                    manifest_path,
+                    cache_files,
+                    dry_run,
+                },
+        } => commands::clean::clean(manifest_path.as_deref(), cache_files, *dry_run),
+        Cli::Futhark {
+            command:
+                Commands::Doctor {
+                    backends,
+                    cuda_home,
+                },
+        } => commands::doctor::doctor(backends, cuda_home.as_deref()),
     }
 }
@@ -7,9 +7,22 @@ use crate::manifest::ValueType;
 pub struct ArrayType {
     pub elements_type: ValueType,
     pub rank: usize,
+    pub ops: ArrayOps,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArrayOps {
+    pub index: bool,
+    pub new_raw: bool,
+    pub values_raw: bool,
 }
 
 impl ArrayType {
+    /// Name of the type in Futhark, like `[][]f32`.
+    pub fn name(&self) -> String {
+        format!("{}{}", "[]".repeat(self.rank), self.elements_type.name())
+    }
+
     pub fn struct_ident(&self) -> Ident {
         format_ident!(
             "Array_{}_{}D",
@@ -38,6 +51,26 @@ impl ArrayType {
         format_ident!("futhark_shape_{}_{}d", self.elements_type.name(), self.rank)
     }
 
+    pub fn fn_index_ident(&self) -> Ident {
+        format_ident!("futhark_index_{}_{}d", self.elements_type.name(), self.rank)
+    }
+
+    pub fn fn_new_raw_ident(&self) -> Ident {
+        format_ident!(
+            "futhark_new_raw_{}_{}d",
+            self.elements_type.name(),
+            self.rank
+        )
+    }
+
+    pub fn fn_values_raw_ident(&self) -> Ident {
+        format_ident!(
+            "futhark_values_raw_{}_{}d",
+            self.elements_type.name(),
+            self.rank
+        )
+    }
+
     pub fn fn_free_ident(&self) -> Ident {
         format_ident!("futhark_free_{}_{}d", self.elements_type.name(), self.rank)
     }
@@ -1,13 +1,32 @@
+use std::collections::HashSet;
+
 use proc_macro2::Ident;
 use quote::format_ident;
 
-use crate::manifest::Type;
+use crate::{manifest::Type, source::TestCase};
 
 #[derive(Debug, Clone)]
 pub struct EntryPoint {
     pub name: String,
-    pub inputs: Vec<Type>,
-    pub outputs: Vec<Type>,
+    pub doc: Option<String>,
+    pub inputs: Vec<Input>,
+    pub outputs: Vec<Output>,
+    /// Cases of the `-- ==` test blocks naming this entry point.
+    pub tests: Vec<TestCase>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Input {
+    pub name: Option<String>,
+    pub typ: Type,
+}
+
+/// Output of an entry point.
+///
+/// Futhark manifests do not name outputs, see [`EntryPoint::output_idents`] for their names.
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub typ: Type,
 }
 
 impl EntryPoint {
@@ -18,4 +37,223 @@ impl EntryPoint {
     pub fn context_fn_ident(&self) -> Ident {
         format_ident!("entry_{}", self.name)
     }
+
+    /// Returns one identifier per output, also used for the fields of the output struct.
+    ///
+    /// Outputs of named opaque types, including records, are named after their type,
+    /// like `stats`. All other outputs fall back to `out_{position}`.
+    pub fn output_idents(&self) -> Vec<Ident> {
+        let names = self.outputs.iter().map(|output| match &output.typ {
+            Type::Opaque(opaque) if is_plain_name(&opaque.name) => Some(opaque.name.as_str()),
+            _ => None,
+        });
+
+        unique_idents(names, "out", GENERATED_NAMES.map(String::from).into())
+    }
+
+    pub fn output_struct_ident(&self) -> Ident {
+        format_ident!("Entry{}Output", camel_case(&self.name))
+    }
+
+    /// Returns one identifier per input.
+    ///
+    /// Futhark parameter names are sanitized into valid and unique Rust identifiers.
+    /// Inputs without a name fall back to `in_{position}`.
+    pub fn input_idents(&self) -> Vec<Ident> {
+        let taken = self
+            .output_idents()
+            .iter()
+            .map(ToString::to_string)
+            .chain(GENERATED_NAMES.map(String::from))
+            .collect();
+
+        unique_idents(
+            self.inputs.iter().map(|input| input.name.as_deref()),
+            "in",
+            taken,
+        )
+    }
+}
+
+/// Names of the locals in generated entry point functions.
+const GENERATED_NAMES: [&str; 4] = ["ctx", "status", "handler", "outputs"];
+
+/// Whether `name` is a plain Futhark name, unlike `(f32, i32)` or `{x: f32}`.
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn unique_idents<'a>(
+    names: impl Iterator<Item = Option<&'a str>>,
+    fallback: &str,
+    mut taken: HashSet<String>,
+) -> Vec<Ident> {
+    names
+        .enumerate()
+        .map(|(i, name)| {
+            let base = match name {
+                Some(name) => sanitize_ident(name),
+                None => format!("{fallback}_{i}"),
+            };
+
+            // Count up from the position until the name is free.
+            let mut name = base.clone();
+            let mut suffix = i;
+            while taken.contains(&name) {
+                name = format!("{base}_{suffix}");
+                suffix += 1;
+            }
+
+            taken.insert(name.clone());
+            format_ident!("{}", name)
+        })
+        .collect()
+}
+
+fn camel_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+fn sanitize_ident(name: &str) -> String {
+    let mut ident = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+
+    // `_` is only a pattern, it can not be used to pass the argument on.
+    if ident.is_empty() {
+        ident.push_str("_0");
+    } else if ident == "_" || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+
+    if RUST_KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    }
+
+    ident
+}
+
+/// Rust keywords, including reserved ones, which can not be used as plain identifiers.
+pub const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{OpaqueType, ValueType};
+
+    fn entry_point(inputs: &[Option<&str>], outputs: usize) -> EntryPoint {
+        let typ = Type::Value(ValueType::f64);
+
+        EntryPoint {
+            name: "test".to_string(),
+            doc: None,
+            inputs: inputs
+                .iter()
+                .map(|name| Input {
+                    name: name.map(String::from),
+                    typ: typ.clone(),
+                })
+                .collect(),
+            outputs: (0..outputs).map(|_| Output { typ: typ.clone() }).collect(),
+            tests: Vec::new(),
+        }
+    }
+
+    fn names(idents: Vec<Ident>) -> Vec<String> {
+        idents.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn sanitize_escapes_keywords() {
+        assert_eq!(sanitize_ident("type"), "type_");
+        assert_eq!(sanitize_ident("fn"), "fn_");
+        assert_eq!(sanitize_ident("self"), "self_");
+        assert_eq!(sanitize_ident("Self"), "Self_");
+        assert_eq!(sanitize_ident("types"), "types");
+    }
+
+    #[test]
+    fn sanitize_replaces_primes_and_invalid_starts() {
+        assert_eq!(sanitize_ident("x'"), "x_");
+        assert_eq!(sanitize_ident("x''"), "x__");
+        assert_eq!(sanitize_ident("1st"), "_1st");
+        assert_eq!(sanitize_ident("_"), "__");
+        assert_eq!(sanitize_ident(""), "_0");
+    }
+
+    #[test]
+    fn inputs_avoid_generated_names() {
+        let ep = entry_point(
+            &[Some("ctx"), Some("status"), Some("out_0"), Some("out_1")],
+            1,
+        );
+
+        assert_eq!(
+            names(ep.input_idents()),
+            ["ctx_0", "status_1", "out_0_2", "out_1"]
+        );
+    }
+
+    #[test]
+    fn inputs_are_unique() {
+        let ep = entry_point(
+            &[Some("x'"), Some("x_"), None, Some("in_2"), Some("match")],
+            0,
+        );
+
+        assert_eq!(
+            names(ep.input_idents()),
+            ["x_", "x__1", "in_2", "in_2_3", "match_"]
+        );
+    }
+
+    #[test]
+    fn outputs_are_named_after_opaque_types() {
+        let opaque = |name: &str| Output {
+            typ: Type::Opaque(OpaqueType {
+                name: name.to_string(),
+                c_name: "futhark_opaque_t".to_string(),
+                doc: None,
+            }),
+        };
+        let mut ep = entry_point(&[Some("stats")], 0);
+        ep.outputs = vec![
+            opaque("stats"),
+            Output {
+                typ: Type::Value(ValueType::f64),
+            },
+            opaque("stats"),
+            opaque("(f64, f64)"),
+            opaque("status"),
+        ];
+
+        assert_eq!(
+            names(ep.output_idents()),
+            ["stats", "out_1", "stats_2", "out_3", "status_4"]
+        );
+        assert_eq!(names(ep.input_idents()), ["stats_0"]);
+    }
+
+    #[test]
+    fn renamed_inputs_are_unique() {
+        let ep = entry_point(&[Some("x_2"), Some("x"), Some("x")], 0);
+        assert_eq!(names(ep.input_idents()), ["x_2", "x", "x_3"]);
+
+        let ep = entry_point(&[Some("out"), Some("out")], 2);
+        assert_eq!(names(ep.input_idents()), ["out", "out_2"]);
+    }
 }
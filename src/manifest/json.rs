@@ -3,7 +3,9 @@ use std::collections::HashMap;
 use eyre::{bail, Context};
 use serde_json::{Map, Value};
 
-use crate::manifest::{ArrayType, EntryPoint, Manifest, Type, ValueType};
+use crate::manifest::{
+    ArrayOps, ArrayType, EntryPoint, Input, Manifest, OpaqueType, Output, Type, ValueType,
+};
 
 pub fn load(manifest_file_content: &str) -> eyre::Result<Manifest> {
     let json: serde_json::Value =
@@ -23,6 +25,7 @@ pub fn load(manifest_file_content: &str) -> eyre::Result<Manifest> {
 
         match kind {
             "array" => types.insert(name.clone(), Type::Array(load_array(typ))),
+            "opaque" => types.insert(name.clone(), Type::Opaque(load_opaque(name, typ)?)),
             _ => bail!("Types of kind {kind} are not supported."),
         };
     }
@@ -44,12 +47,36 @@ fn load_array(obj: &Map<String, Value>) -> ArrayType {
     let elements = ValueType::from_manifest(obj["elemtype"].as_str().unwrap()).unwrap();
     let rank = obj["rank"].as_i64().unwrap() as usize;
 
+    // Older Futhark versions do not list any operations.
+    let has_op = |op: &str| obj.get("ops").and_then(|ops| ops.get(op)).is_some();
+    let ops = ArrayOps {
+        index: has_op("index"),
+        new_raw: has_op("new_raw"),
+        values_raw: has_op("values_raw"),
+    };
+
     ArrayType {
         elements_type: elements,
         rank,
+        ops,
     }
 }
 
+fn load_opaque(name: &str, obj: &Map<String, Value>) -> eyre::Result<OpaqueType> {
+    let c_type = obj["ctype"].as_str().unwrap();
+    let c_name = c_type
+        .strip_prefix("struct ")
+        .and_then(|c_type| c_type.strip_suffix('*'))
+        .map(str::trim)
+        .ok_or_else(|| eyre::eyre!("Unexpected C type {c_type} for opaque type {name}."))?;
+
+    Ok(OpaqueType {
+        name: name.to_string(),
+        c_name: c_name.to_string(),
+        doc: None,
+    })
+}
+
 fn load_entry_point(
     name: &str,
     obj: &Map<String, Value>,
@@ -61,21 +88,68 @@ fn load_entry_point(
         .as_array()
         .unwrap()
         .iter()
-        .map(|input| input["type"].as_str().unwrap())
-        .map(|input_type| types[input_type])
+        .map(|input| Input {
+            name: input["name"].as_str().map(String::from),
+            typ: types[input["type"].as_str().unwrap()].clone(),
+        })
         .collect::<Vec<_>>();
 
     let outputs = obj["outputs"]
         .as_array()
         .unwrap()
         .iter()
-        .map(|input| input["type"].as_str().unwrap())
-        .map(|input_type| types[input_type])
+        .map(|output| Output {
+            typ: types[output["type"].as_str().unwrap()].clone(),
+        })
         .collect::<Vec<_>>();
 
     EntryPoint {
         name,
+        doc: None,
         inputs,
         outputs,
+        tests: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn array_type(ops: &str) -> ArrayType {
+        let json = format!(
+            r#"{{
+                "types": {{
+                    "[]f64": {{ "kind": "array", "elemtype": "f64", "rank": 1, "ctype": "struct futhark_f64_1d *"{ops} }}
+                }},
+                "entry_points": {{}}
+            }}"#
+        );
+
+        load(&json)
+            .unwrap()
+            .types
+            .into_iter()
+            .find_map(|typ| match typ {
+                Type::Array(array) => Some(array),
+                _ => None,
+            })
+            .expect("array type is missing")
+    }
+
+    #[test]
+    fn arrays_without_ops() {
+        let array = array_type("");
+
+        assert!(!array.ops.index && !array.ops.new_raw && !array.ops.values_raw);
+    }
+
+    #[test]
+    fn arrays_with_ops() {
+        let array = array_type(
+            r#", "ops": { "index": "futhark_index_f64_1d", "values_raw": "futhark_values_raw_f64_1d" }"#,
+        );
+
+        assert!(array.ops.index && !array.ops.new_raw && array.ops.values_raw);
     }
 }
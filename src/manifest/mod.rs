@@ -2,16 +2,21 @@ use std::{fs, path::Path};
 
 use eyre::{Context, Result};
 
+use crate::source::{DocComments, TestBlock};
+
 mod json;
 
 mod value_type;
 pub use value_type::ValueType;
 
 mod array_type;
-pub use array_type::ArrayType;
+pub use array_type::{ArrayOps, ArrayType};
+
+mod opaque_type;
+pub use opaque_type::OpaqueType;
 
 mod entry_point;
-pub use entry_point::EntryPoint;
+pub use entry_point::{EntryPoint, Input, Output, RUST_KEYWORDS};
 
 #[derive(Debug, Clone)]
 pub struct Manifest {
@@ -25,10 +30,44 @@ impl Manifest {
 
         json::load(&json)
     }
+
+    pub fn attach_docs(&mut self, docs: &DocComments) {
+        for entry_point in &mut self.entry_points {
+            entry_point.doc = docs.entry_point(&entry_point.name).map(String::from);
+        }
+
+        for typ in &mut self.types {
+            if let Type::Opaque(opaque) = typ {
+                opaque.doc = docs.type_alias(&opaque.name).map(String::from);
+            }
+        }
+    }
+
+    pub fn attach_tests(&mut self, blocks: &[TestBlock]) {
+        for entry_point in &mut self.entry_points {
+            entry_point.tests = blocks
+                .iter()
+                .filter(|block| block.entry_points.contains(&entry_point.name))
+                .flat_map(|block| block.cases.iter().cloned())
+                .collect();
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Type {
     Value(ValueType),
     Array(ArrayType),
+    Opaque(OpaqueType),
+}
+
+impl Type {
+    /// Name of the type in Futhark.
+    pub fn name(&self) -> String {
+        match self {
+            Type::Value(value) => value.name().to_string(),
+            Type::Array(array) => array.name(),
+            Type::Opaque(opaque) => opaque.name.clone(),
+        }
+    }
 }
@@ -0,0 +1,37 @@
+use proc_macro2::Ident;
+use quote::format_ident;
+
+#[derive(Debug, Clone)]
+pub struct OpaqueType {
+    pub name: String,
+    pub c_name: String,
+    pub doc: Option<String>,
+}
+
+impl OpaqueType {
+    fn suffix(&self) -> &str {
+        self.c_name
+            .strip_prefix("futhark_opaque_")
+            .unwrap_or(&self.c_name)
+    }
+
+    pub fn struct_ident(&self) -> Ident {
+        format_ident!("Opaque_{}", self.suffix())
+    }
+
+    pub fn type_ident(&self) -> Ident {
+        format_ident!("{}", self.c_name)
+    }
+
+    pub fn fn_store_ident(&self) -> Ident {
+        format_ident!("futhark_store_opaque_{}", self.suffix())
+    }
+
+    pub fn fn_restore_ident(&self) -> Ident {
+        format_ident!("futhark_restore_opaque_{}", self.suffix())
+    }
+
+    pub fn fn_free_ident(&self) -> Ident {
+        format_ident!("futhark_free_opaque_{}", self.suffix())
+    }
+}
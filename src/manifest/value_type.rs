@@ -68,6 +68,10 @@ impl ValueType {
         }
     }
 
+    pub fn is_float(&self) -> bool {
+        matches!(self, ValueType::f16 | ValueType::f32 | ValueType::f64)
+    }
+
     pub fn from_manifest(name: &str) -> eyre::Result<Self> {
         match name {
             "i8" => Ok(ValueType::i8),
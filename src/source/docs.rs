@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+/// Doc comments (`-- |`) attached to declarations in a Futhark source file.
+#[derive(Debug, Clone, Default)]
+pub struct DocComments {
+    entry_points: HashMap<String, String>,
+    type_aliases: HashMap<String, String>,
+}
+
+impl DocComments {
+    pub fn entry_point(&self, name: &str) -> Option<&str> {
+        self.entry_points.get(name).map(String::as_str)
+    }
+
+    pub fn type_alias(&self, name: &str) -> Option<&str> {
+        self.type_aliases.get(name).map(String::as_str)
+    }
+
+    /// Adds the type docs of `other`, keeping existing ones.
+    pub fn add_type_aliases(&mut self, other: DocComments) {
+        for (name, doc) in other.type_aliases {
+            self.type_aliases.entry(name).or_insert(doc);
+        }
+    }
+}
+
+pub fn parse(source: &str) -> DocComments {
+    let mut docs = DocComments::default();
+    let mut pending: Option<Vec<&str>> = None;
+    let mut in_test_block = false;
+
+    for line in source.lines().map(str::trim) {
+        if let Some(first) = line.strip_prefix("-- |") {
+            pending = Some(vec![strip_space(first)]);
+            in_test_block = false;
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("--") {
+            // Test blocks (`-- ==`) end the doc comment.
+            if rest.trim() == "==" {
+                in_test_block = true;
+            }
+
+            if let (Some(lines), false) = (&mut pending, in_test_block) {
+                lines.push(strip_space(rest));
+            }
+            continue;
+        }
+
+        in_test_block = false;
+
+        // Attributes may sit between the doc comment and its declaration.
+        if line.starts_with("#[") {
+            continue;
+        }
+
+        let Some(lines) = pending.take() else {
+            continue;
+        };
+
+        let doc = text_fences(lines.join("\n").trim());
+
+        if let Some(name) = declared_name(line, "entry") {
+            docs.entry_points.insert(name.to_string(), doc);
+        } else if let Some(name) = ["type", "type^", "type~"]
+            .into_iter()
+            .find_map(|keyword| declared_name(line, keyword))
+        {
+            docs.type_aliases.insert(name.to_string(), doc);
+        }
+    }
+
+    docs
+}
+
+/// Marks bare code fences as `text`, as rustdoc would run them as Rust doctests.
+fn text_fences(doc: &str) -> String {
+    let mut in_code = false;
+
+    doc.lines()
+        .map(|line| {
+            let fence = line.trim();
+            if !fence.starts_with("```") {
+                return line.to_string();
+            }
+
+            in_code = !in_code;
+            if in_code && fence == "```" {
+                format!("{}text", line.trim_end())
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn strip_space(line: &str) -> &str {
+    line.strip_prefix(' ').unwrap_or(line)
+}
+
+fn declared_name<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(keyword)?;
+
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let name = rest
+        .trim_start()
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ':' || c == '=')
+        .next()?;
+
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_multi_line_comments() {
+        let docs = parse(
+            "-- | Computes the mean.\n\
+             --\n\
+             -- Returns `nan` for empty arrays.\n\
+             entry mean (xs: []f64) : f64 = f64.sum xs / f64.i64 (length xs)\n",
+        );
+
+        assert_eq!(
+            docs.entry_point("mean"),
+            Some("Computes the mean.\n\nReturns `nan` for empty arrays.")
+        );
+    }
+
+    #[test]
+    fn code_blocks_are_not_doctests() {
+        let docs = parse(
+            "-- | Sums the values:\n\
+             --\n\
+             -- ```\n\
+             -- sum [1, 2]\n\
+             -- ```\n\
+             --\n\
+             -- ```futhark\n\
+             -- sum []\n\
+             -- ```\n\
+             entry sum (xs: []i32) = i32.sum xs\n",
+        );
+
+        assert_eq!(
+            docs.entry_point("sum"),
+            Some("Sums the values:\n\n```text\nsum [1, 2]\n```\n\n```futhark\nsum []\n```")
+        );
+    }
+
+    #[test]
+    fn blank_lines_separate_comments() {
+        let docs = parse(
+            "-- | Belongs to nothing.\n\
+             \n\
+             entry first (x: i32) = x\n\
+             \n\
+             -- | Second.\n\
+             entry second (x: i32) = x\n",
+        );
+
+        assert_eq!(docs.entry_point("first"), None);
+        assert_eq!(docs.entry_point("second"), Some("Second."));
+    }
+
+    #[test]
+    fn skips_attributes() {
+        let docs = parse(
+            "-- | Inlined.\n\
+             #[inline]\n\
+             entry inlined (x: i32) = x\n\
+             -- | A state.\n\
+             #[unsafe]\n\
+             type~ state = []i32\n",
+        );
+
+        assert_eq!(docs.entry_point("inlined"), Some("Inlined."));
+        assert_eq!(docs.type_alias("state"), Some("A state."));
+    }
+
+    #[test]
+    fn stops_before_test_blocks() {
+        let docs = parse(
+            "-- | Doubles `x`.\n\
+             -- ==\n\
+             -- input { 1 }\n\
+             -- output { 2 }\n\
+             entry double (x: i32) = x * 2\n",
+        );
+
+        assert_eq!(docs.entry_point("double"), Some("Doubles `x`."));
+    }
+
+    #[test]
+    fn ignores_other_declarations() {
+        let docs = parse(
+            "-- | A helper.\n\
+             def helper (x: i32) = x\n\
+             entry main (x: i32) = helper x\n",
+        );
+
+        assert_eq!(docs.entry_point("main"), None);
+        assert_eq!(docs.entry_point("helper"), None);
+    }
+}
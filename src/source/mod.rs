@@ -0,0 +1,132 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{Context, Result};
+
+mod docs;
+pub use docs::DocComments;
+
+mod spec;
+pub use spec::{Outcome, TestBlock, TestCase};
+
+/// A Futhark source file.
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub content: String,
+    /// Content of the files imported by this file, directly or indirectly.
+    pub imports: Vec<String>,
+}
+
+impl Source {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).wrap_err("Failed to read Futhark source file.")?;
+
+        let mut visited = vec![path.canonicalize().unwrap_or_else(|_| path.to_path_buf())];
+        let mut imports = Vec::new();
+        read_imports(path, &content, &mut visited, &mut imports);
+
+        Ok(Source { content, imports })
+    }
+
+    /// Doc comments of the entry points in this file and of the types in all files.
+    ///
+    /// Futhark only treats `entry` declarations of the main file as entry points,
+    /// but types are often defined in imported files.
+    pub fn doc_comments(&self) -> DocComments {
+        let mut docs = docs::parse(&self.content);
+
+        for import in &self.imports {
+            docs.add_type_aliases(docs::parse(import));
+        }
+
+        docs
+    }
+
+    pub fn test_blocks(&self) -> Vec<TestBlock> {
+        spec::parse(&self.content)
+    }
+}
+
+/// Reads the files imported by `content` recursively.
+///
+/// Imports that can not be read, like those of packages that were not synced
+/// with `futhark pkg sync`, are skipped.
+fn read_imports(path: &Path, content: &str, visited: &mut Vec<PathBuf>, imports: &mut Vec<String>) {
+    let dir = path.parent().unwrap_or(Path::new("."));
+
+    for import in import_paths(content) {
+        let path = dir.join(format!("{import}.fut"));
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if visited.contains(&canonical) {
+            continue;
+        }
+        visited.push(canonical);
+
+        if let Ok(content) = fs::read_to_string(&path) {
+            read_imports(&path, &content, visited, imports);
+            imports.push(content);
+        }
+    }
+}
+
+/// Returns the paths of `import "..."` declarations, which are relative and lack `.fut`.
+fn import_paths(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .map(|line| line.split("--").next().unwrap_or_default())
+        .flat_map(|line| {
+            line.match_indices("import")
+                .map(move |(i, _)| &line[i + 6..])
+        })
+        .filter_map(|rest| {
+            let rest = rest.trim_start().strip_prefix('"')?;
+            rest.split_once('"').map(|(path, _)| path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_imports() {
+        let content = "import \"lib/github.com/diku-dk/sorts/radix_sort\"\n\
+                       open import \"types\" -- import \"commented\"\n\
+                       module m = import \"other\"\n\
+                       -- import \"ignored\"\n\
+                       let important = 1\n";
+
+        assert_eq!(
+            import_paths(content),
+            ["lib/github.com/diku-dk/sorts/radix_sort", "types", "other"]
+        );
+    }
+
+    #[test]
+    fn reads_type_docs_from_imports() -> Result<()> {
+        let dir =
+            std::env::temp_dir().join(format!("cargo-futhark-imports-{}", std::process::id()));
+        fs::create_dir_all(dir.join("util"))?;
+        fs::write(
+            dir.join("main.fut"),
+            "import \"util/types\"\n\n-- | Runs it.\nentry run (s: state) : state = s\n",
+        )?;
+        fs::write(
+            dir.join("util").join("types.fut"),
+            "import \"../main\"\n\n-- | The state.\ntype state = {x: i32}\n\n-- | Not an entry point.\nentry run (x: i32) = x\n",
+        )?;
+
+        let source = Source::from_file(dir.join("main.fut"));
+        fs::remove_dir_all(&dir)?;
+        let docs = source?.doc_comments();
+
+        assert_eq!(docs.entry_point("run"), Some("Runs it."));
+        assert_eq!(docs.type_alias("state"), Some("The state."));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,350 @@
+/// A test block (`-- ==`) in a Futhark source file, as used by `futhark test`.
+#[derive(Debug, Clone, Default)]
+pub struct TestBlock {
+    /// Entry points given with `entry:`, `main` if there are none.
+    pub entry_points: Vec<String>,
+    pub cases: Vec<TestCase>,
+}
+
+/// A single `input` of a test block.
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    /// Input values in Futhark's textual value format.
+    pub inputs: Vec<String>,
+    pub outcome: Outcome,
+    /// Tags of the surrounding block, like `disable` or `no_opencl`.
+    pub tags: Vec<String>,
+    /// Reason why the case can not be run through the bindings.
+    pub unsupported: Option<&'static str>,
+    /// Whether the case is excluded from benchmarks, by `nobench` or the `nobench` tag.
+    pub nobench: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// The entry point must succeed.
+    Success,
+    /// The entry point must return these values in Futhark's textual value format.
+    Values(Vec<String>),
+    /// The entry point must fail.
+    Error,
+}
+
+pub fn parse(source: &str) -> Vec<TestBlock> {
+    let mut blocks = Vec::new();
+    let mut lines = source.lines().map(str::trim).peekable();
+
+    while let Some(line) = lines.next() {
+        if comment_text(line) != Some("==") {
+            continue;
+        }
+
+        let mut body = String::new();
+        while let Some(text) = lines.peek().and_then(|line| comment_text(line)) {
+            if text.starts_with('|') {
+                break;
+            }
+
+            body.push_str(text);
+            body.push('\n');
+            lines.next();
+        }
+
+        blocks.push(parse_block(&body));
+    }
+
+    blocks
+}
+
+fn comment_text(line: &str) -> Option<&str> {
+    line.strip_prefix("--").map(str::trim)
+}
+
+fn parse_block(body: &str) -> TestBlock {
+    let mut block = TestBlock::default();
+    let mut tags = Vec::new();
+    let mut unsupported = None;
+    let mut nobench = false;
+    let mut rest = body;
+
+    loop {
+        // Skip braces that do not belong to a known keyword.
+        if let Some((_, after)) = braced(rest) {
+            rest = after;
+            continue;
+        }
+
+        let Some((word, after)) = next_word(rest) else {
+            break;
+        };
+        rest = after;
+
+        match word {
+            "tags" => {
+                if let Some((inner, after)) = braced(rest) {
+                    tags.extend(inner.split_whitespace().map(String::from));
+                    rest = after;
+                }
+            }
+            "entry:" => {
+                let (line, after) = rest.split_once('\n').unwrap_or((rest, ""));
+                block
+                    .entry_points
+                    .extend(line.split_whitespace().map(String::from));
+                rest = after;
+            }
+            "random" => unsupported = Some("random inputs are not supported"),
+            "script" => unsupported = Some("script inputs are not supported"),
+            "nobench" => nobench = true,
+            "input" => {
+                let (inputs, after) = data(rest);
+                rest = after;
+
+                let (inputs, reason) = match inputs {
+                    Ok(inputs) => (inputs, unsupported.take()),
+                    Err(reason) => (Vec::new(), Some(reason)),
+                };
+                block.cases.push(TestCase {
+                    inputs,
+                    outcome: Outcome::Success,
+                    tags: Vec::new(),
+                    unsupported: reason,
+                    nobench: std::mem::take(&mut nobench),
+                });
+            }
+            "output" => {
+                let (outputs, after) = data(rest);
+                rest = after;
+
+                if let Some(case) = block.cases.last_mut() {
+                    match outputs {
+                        Ok(outputs) => case.outcome = Outcome::Values(outputs),
+                        Err(reason) => case.unsupported = case.unsupported.or(Some(reason)),
+                    }
+                }
+            }
+            "auto" => {
+                // Skip the `output` that follows.
+                rest = next_word(rest).map_or("", |(_, after)| after);
+
+                if let Some(case) = block.cases.last_mut() {
+                    case.unsupported = case
+                        .unsupported
+                        .or(Some("automatic outputs are not supported"));
+                }
+            }
+            "error:" => {
+                rest = rest.split_once('\n').map_or("", |(_, after)| after);
+
+                if let Some(case) = block.cases.last_mut() {
+                    case.outcome = Outcome::Error;
+                }
+            }
+            "structure" => {
+                // The backend is optional, like in `structure gpu { SegMap 1 }`.
+                let mut text = rest;
+                if let Some((word, after)) = next_word(text) {
+                    if word != "{" {
+                        text = after;
+                    }
+                }
+                if let Some((_, after)) = braced(text) {
+                    rest = after;
+                }
+            }
+            // Descriptions and modifiers like `compiled`.
+            _ => {}
+        }
+    }
+
+    if block.entry_points.is_empty() {
+        block.entry_points.push("main".to_string());
+    }
+
+    for case in &mut block.cases {
+        case.tags = tags.clone();
+        case.nobench |= tags.iter().any(|tag| tag == "nobench");
+    }
+
+    block
+}
+
+/// Splits the first word off `text`.
+///
+/// `{`, `}` and `@` end words and are returned as words of their own,
+/// so unknown text always makes progress.
+fn next_word(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    let first = text.chars().next()?;
+    let end = match first {
+        '{' | '}' | '@' => first.len_utf8(),
+        _ => text
+            .find(|c: char| c.is_whitespace() || matches!(c, '{' | '}' | '@'))
+            .unwrap_or(text.len()),
+    };
+
+    Some((&text[..end], &text[end..]))
+}
+
+/// Splits `{ ... }` at the start of `text` into its content and the rest.
+fn braced(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start().strip_prefix('{')?;
+    let mut depth = 0;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some((&text[..i], &text[i + 1..])),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Parses the values of an `input` or `output`.
+fn data(text: &str) -> (Result<Vec<String>, &'static str>, &str) {
+    if let Some((inner, after)) = braced(text) {
+        return (Ok(split_values(inner)), after);
+    }
+
+    match text.trim_start().strip_prefix('@') {
+        Some(file) => {
+            let after = next_word(file).map_or("", |(_, after)| after);
+            (Err("data files are not supported"), after)
+        }
+        None => (Err("invalid test data"), text),
+    }
+}
+
+/// Splits whitespace separated values, keeping arrays and `empty(...)` together.
+fn split_values(text: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+
+    for c in text.chars() {
+        match c {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+
+        if c.is_whitespace() && depth == 0 {
+            if !current.is_empty() {
+                values.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(c);
+        }
+    }
+
+    if !current.is_empty() {
+        values.push(current);
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(body: &str) -> TestBlock {
+        parse_block(&format!("{body}\n"))
+    }
+
+    #[test]
+    fn reads_inputs_and_outputs() {
+        let block = block("input { [1.0, 2.0] 3i32 } output { 1.5 }\ninput { [] 0i32 }");
+
+        assert_eq!(block.entry_points, ["main"]);
+        assert_eq!(block.cases.len(), 2);
+        assert_eq!(block.cases[0].inputs, ["[1.0, 2.0]", "3i32"]);
+        assert!(matches!(&block.cases[0].outcome, Outcome::Values(values) if values == &["1.5"]));
+        assert!(matches!(block.cases[1].outcome, Outcome::Success));
+    }
+
+    #[test]
+    fn reads_tags_and_entry_points() {
+        let block = block("tags { disable no_opencl }\nentry: average sum\ninput { 1 }");
+
+        assert_eq!(block.entry_points, ["average", "sum"]);
+        assert_eq!(block.cases[0].tags, ["disable", "no_opencl"]);
+        assert!(!block.cases[0].nobench);
+    }
+
+    #[test]
+    fn nobench_tag_excludes_every_case() {
+        let block = block("tags { nobench }\ninput { 1 }\ninput { 2 }");
+
+        assert!(block.cases.iter().all(|case| case.nobench));
+    }
+
+    #[test]
+    fn marks_auto_outputs_and_errors() {
+        let block =
+            block("input { 1 } auto output\ninput { 2 } error: division by zero\ninput { 3 }");
+
+        assert_eq!(block.cases.len(), 3);
+        assert_eq!(
+            block.cases[0].unsupported,
+            Some("automatic outputs are not supported")
+        );
+        assert!(matches!(block.cases[1].outcome, Outcome::Error));
+        assert!(matches!(block.cases[2].outcome, Outcome::Success));
+    }
+
+    #[test]
+    fn marks_random_and_script_inputs() {
+        let block =
+            block("random input { [10]f32 }\nscript input { mk_input 10 }\nnobench input { 1 }");
+
+        assert_eq!(
+            block.cases[0].unsupported,
+            Some("random inputs are not supported")
+        );
+        assert_eq!(
+            block.cases[1].unsupported,
+            Some("script inputs are not supported")
+        );
+        assert_eq!(block.cases[2].unsupported, None);
+        assert!(block.cases[2].nobench && !block.cases[0].nobench);
+    }
+
+    #[test]
+    fn skips_structure_and_stray_braces() {
+        let block = block(
+            "structure gpu { SegMap 1 }\nstructure { Screma 2 }\n{ stray }\ninput { 1 } output { 2 }",
+        );
+
+        assert_eq!(block.cases.len(), 1);
+        assert_eq!(block.cases[0].inputs, ["1"]);
+    }
+
+    #[test]
+    fn reads_every_block() {
+        let source = "\
+-- ==
+-- entry: first
+-- input { 1 }
+
+-- | Docs.
+-- ==
+-- entry: second
+-- structure gpu { SegMap 1 }
+-- input { 2 }
+-- input { 3 }
+entry first x = x
+";
+
+        let blocks = parse(source);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].entry_points, ["first"]);
+        assert_eq!(blocks[0].cases.len(), 1);
+        assert_eq!(blocks[1].entry_points, ["second"]);
+        assert_eq!(blocks[1].cases.len(), 2);
+    }
+}
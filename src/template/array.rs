@@ -1,9 +1,9 @@
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 
-use crate::manifest::ArrayType;
+use crate::{manifest::ArrayType, template::Options};
 
-pub fn template(typ: &ArrayType) -> TokenStream {
+pub fn template(typ: &ArrayType, options: &Options) -> TokenStream {
     let rank = typ.rank;
     let struct_name = typ.struct_ident();
     let type_name = typ.type_ident();
@@ -12,6 +12,7 @@ pub fn template(typ: &ArrayType) -> TokenStream {
     let fn_values_name = typ.fn_values_ident();
     let fn_free_name = typ.fn_free_ident();
     let elem_typ_name = typ.elements_type.ident();
+    let elem_suffix = typ.elements_type.name();
 
     let summary_doc = format!(
         "Array of type `{}` and rank `{}`.",
@@ -19,10 +20,32 @@ pub fn template(typ: &ArrayType) -> TokenStream {
         typ.rank
     );
 
+    let dim_indices = (0..rank).collect::<Vec<_>>();
+
     let dim_params = (0..rank)
         .map(|i| format_ident!("dim_{i}"))
         .collect::<Vec<_>>();
 
+    let index_fn = if typ.ops.index {
+        index_template(typ)
+    } else {
+        quote!()
+    };
+
+    let raw_fns = raw_template(typ);
+
+    let ndarray_impl = if options.ndarray {
+        ndarray_template(typ)
+    } else {
+        quote!()
+    };
+
+    let serde_impl = if options.serde {
+        serde_template(typ)
+    } else {
+        quote!()
+    };
+
     quote! {
         #[doc = #summary_doc]
         ///
@@ -38,51 +61,189 @@ pub fn template(typ: &ArrayType) -> TokenStream {
             /// Create a new Futhark array from a flat buffer.
             ///
             ///  Multi-dimensional arrays are expect row-major form.
-            #[allow(clippy::identity_op)]
+            ///
+            /// # Panics
+            /// Panics if the length of `data` does not match the dimensions,
+            /// if a dimension does not fit into an `i64` or if Futhark fails to allocate the array.
+            /// Use [`try_new`](Self::try_new) to handle these errors instead.
             pub fn new(context: &'c Context<B>, data: &[#elem_typ_name], #(#dim_params: usize),*) -> Self {
-                assert_eq!(#(#dim_params *)* 1, data.len());
+                Self::try_new(context, data, #(#dim_params),*).expect("Failed to create Futhark array.")
+            }
+
+            /// Create a new Futhark array from a flat buffer.
+            ///
+            ///  Multi-dimensional arrays are expect row-major form.
+            pub fn try_new(context: &'c Context<B>, data: &[#elem_typ_name], #(#dim_params: usize),*) -> Result<Self, Error> {
+                let dims = [#(#dim_params),*];
+
+                let len = dims
+                    .iter()
+                    .try_fold(1usize, |len, &dim| len.checked_mul(dim))
+                    .ok_or(Error::DimensionOverflow)?;
+                if len != data.len() {
+                    return Err(Error::LengthMismatch {
+                        expected: len,
+                        actual: data.len(),
+                    });
+                }
+
+                let mut futhark_dims = [0i64; #rank];
+                for (futhark_dim, dim) in futhark_dims.iter_mut().zip(dims) {
+                    *futhark_dim = i64::try_from(dim).map_err(|_| Error::DimensionOverflow)?;
+                }
 
                 let inner = unsafe {
                     B::#fn_new_name(
                         context.inner,
                         data.as_ptr(),
-                        #(#dim_params.try_into().unwrap()),*
+                        #(futhark_dims[#dim_indices]),*
                     )
                 };
 
-                assert!(!inner.is_null());
-                #struct_name { context, inner }
+                if inner.is_null() {
+                    return Err(Error::Allocation);
+                }
+
+                Ok(#struct_name { context, inner })
+            }
+
+            /// Create a new Futhark array from Futhark's textual value format.
+            ///
+            /// The input must be an array of the same element type and rank,
+            /// like `[1.0f64, 2.0f64]` or `empty([0]f64)`. Type suffixes are optional.
+            pub fn from_textual(context: &'c Context<B>, input: &str) -> Result<Self, textual::ParseError> {
+                let (data, shape) = textual::parse_array::<#elem_typ_name>(input, #rank, #elem_suffix)?;
+
+                Self::try_new(context, &data, #(shape[#dim_indices]),*)
+                    .map_err(|err| textual::ParseError::new(format!("failed to create the array: {err}")))
             }
 
-            /// Returns the arrays shape
+            /// Read a Futhark array in Futhark's binary value format.
+            ///
+            /// The value must have the same element type and rank as this array.
+            /// Leading whitespace is skipped, so consecutive values of a dataset
+            /// can be read one after another.
+            pub fn read_binary(context: &'c Context<B>, reader: &mut impl std::io::Read) -> Result<Self, binary::Error> {
+                let (data, shape) = binary::read_array::<#elem_typ_name>(reader, #rank, #elem_suffix)?;
+
+                Self::try_new(context, &data, #(shape[#dim_indices]),*).map_err(binary::Error::Create)
+            }
+
+            /// Write the array in Futhark's binary value format.
+            ///
+            /// The values are read with [`values_into`](Self::values_into).
+            pub fn write_binary(&self, writer: &mut impl std::io::Write) -> Result<(), binary::Error> {
+                let values = self.to_vec().map_err(binary::Error::Values)?;
+
+                binary::write_array(writer, &values, &self.shape(), #elem_suffix)
+            }
+
+            /// Returns the arrays shape.
             ///
             /// The length of `array.shape()` is its rank.
-            pub fn shape(&self) -> &[usize] {
-                unsafe {
+            ///
+            /// # Panics
+            /// Panics if Futhark reports a dimension that does not fit into a `usize`.
+            pub fn shape(&self) -> [usize; #rank] {
+                let shape = unsafe {
                     let shape = B::#fn_shape_name(self.context.inner, self.inner);
-                    std::slice::from_raw_parts(shape as *const usize, #rank)
+                    std::slice::from_raw_parts(shape, #rank)
+                };
+
+                std::array::from_fn(|i| {
+                    usize::try_from(shape[i]).expect("Futhark reported an invalid array dimension.")
+                })
+            }
+
+            #index_fn
+            #raw_fns
+
+            /// Read the arrays values into `out`.
+            ///
+            /// The length of `out` must be the product of the arrays `shape`.
+            /// Multi-dimensional arrays are written in row-major form.
+            ///
+            /// This waits for the values to be copied using [`Context::sync`],
+            /// so `out` can be used right away. Every other method reading the values,
+            /// like [`to_vec`](Self::to_vec) or the `Display` impl, synchronizes the same way.
+            pub fn values_into(&self, out: &mut [#elem_typ_name]) -> Result<(), Error> {
+                unsafe { self.values_into_unsynced(out)? };
+
+                if !self.context.sync() {
+                    return Err(Error::Sync);
                 }
+
+                Ok(())
+            }
+
+            /// Start reading the arrays values into `out` without synchronizing the [`Context`].
+            ///
+            /// This behaves like [`values_into`](Self::values_into), except that most backends
+            /// copy the values asynchronously. This allows to read multiple arrays
+            /// and to synchronize only once afterwards.
+            ///
+            /// # Safety
+            /// The values might still be written to `out` after this returned.
+            /// `out` must not be accessed, moved or dropped until [`Context::sync`]
+            /// has been called and returned `true`.
+            pub unsafe fn values_into_unsynced(&self, out: &mut [#elem_typ_name]) -> Result<(), Error> {
+                let len = self.shape().iter().product::<usize>();
+                if out.len() != len {
+                    return Err(Error::LengthMismatch {
+                        expected: len,
+                        actual: out.len(),
+                    });
+                }
+
+                Error::check(B::#fn_values_name(self.context.inner, self.inner, out.as_mut_ptr()))
+            }
+
+            /// Read the arrays values into a new vector.
+            ///
+            /// Multi-dimensional arrays are written in row-major form, like with [`values_into`](Self::values_into).
+            pub fn to_vec(&self) -> Result<Vec<#elem_typ_name>, Error> {
+                let len = self.shape().iter().product::<usize>();
+                let mut out = vec![Default::default(); len];
+                self.values_into(&mut out)?;
+
+                Ok(out)
+            }
+
+            /// Formats the array in Futhark's textual value format, like its [`Display`](std::fmt::Display) impl.
+            pub fn to_textual(&self) -> Result<String, Error> {
+                let values = self.to_vec()?;
+
+                Ok(textual::format_array(&values, &self.shape(), #elem_suffix))
             }
 
             /// Read the arrays values to a buffer.
             ///
-            /// The `out` buffer will have the length of the `shape`s product.
-            ///  Multi-dimensional arrays are written in row-major form.
+            /// Unlike with [`values_into`](Self::values_into), the `out` buffer
+            /// will be resized to the length of the `shape`s product.
+            /// Multi-dimensional arrays are written in row-major form.
             ///
-            /// # Important
-            /// Before calling this, you most likely want to call [`Context::sync`] first.
-            /// See the documentation of [`Context::sync`] for more details.
+            /// # Panics
+            /// Panics if reading the values fails. Use [`values_into`](Self::values_into)
+            /// or [`to_vec`](Self::to_vec) to handle errors instead.
             pub fn values(&self, out: &mut Vec<#elem_typ_name>) {
-                let s = self.shape();
-                let len = s.iter().product::<usize>();
+                let len = self.shape().iter().product::<usize>();
+                out.clear();
+                out.resize(len, Default::default());
 
-                out.reserve(len - out.capacity());
-                unsafe {
-                    B::#fn_values_name(self.context.inner, self.inner, out.as_mut_ptr());
-                    out.set_len(len);
-                }
+                self.values_into(out).expect("Failed to read Futhark array values.");
+            }
+        }
+
+        impl<B: Backend> std::fmt::Display for #struct_name <'_, B> {
+            /// Formats the array in Futhark's textual value format.
+            ///
+            /// # Panics
+            /// Panics if reading the values fails.
+            /// Use [`to_textual`](Self::to_textual) to handle errors instead.
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let values = self.to_vec().expect("Failed to read Futhark array values.");
 
-                assert!(self.context.sync());
+                textual::write_array(f, &values, &self.shape(), #elem_suffix)
             }
         }
 
@@ -95,5 +256,280 @@ pub fn template(typ: &ArrayType) -> TokenStream {
                 }
             }
         }
+
+        impl<'c, B: Backend> FutharkArray<'c, B> for #struct_name <'c, B> {
+            type Elem = #elem_typ_name;
+            type Shape = [usize; #rank];
+
+            const RANK: usize = #rank;
+            const ELEM_NAME: &'static str = #elem_suffix;
+
+            fn try_from_shape(context: &'c Context<B>, data: &[#elem_typ_name], shape: [usize; #rank]) -> Result<Self, Error> {
+                #struct_name::try_new(context, data, #(shape[#dim_indices]),*)
+            }
+
+            fn context(&self) -> &'c Context<B> {
+                self.context
+            }
+
+            fn shape(&self) -> [usize; #rank] {
+                #struct_name::shape(self)
+            }
+
+            fn values_into(&self, out: &mut [#elem_typ_name]) -> Result<(), Error> {
+                #struct_name::values_into(self, out)
+            }
+
+            fn to_vec(&self) -> Result<Vec<#elem_typ_name>, Error> {
+                #struct_name::to_vec(self)
+            }
+        }
+
+        #ndarray_impl
+        #serde_impl
+    }
+}
+
+fn ndarray_template(typ: &ArrayType) -> TokenStream {
+    let rank = typ.rank;
+    let struct_name = typ.struct_ident();
+    let elem_typ_name = typ.elements_type.ident();
+
+    let dim_indices = (0..rank).collect::<Vec<_>>();
+
+    let (dimension, to_dimension, check_rank) = if rank <= 6 {
+        let ix = format_ident!("Ix{rank}");
+        (
+            quote!(ndarray::#ix),
+            quote!(ndarray::Dim([#(shape[#dim_indices]),*])),
+            quote!(),
+        )
+    } else {
+        (
+            quote!(ndarray::IxDyn),
+            quote!(ndarray::IxDyn(&shape)),
+            quote! {
+                if shape.len() != #rank {
+                    return Err(Error::RankMismatch {
+                        expected: #rank,
+                        actual: shape.len(),
+                    });
+                }
+            },
+        )
+    };
+
+    quote! {
+        impl<'c, B: Backend> #struct_name <'c, B> {
+            /// Create a new Futhark array from an `ndarray` array or view.
+            ///
+            /// Arrays in standard (row-major) layout are passed on directly,
+            /// all other layouts are copied into row-major order first.
+            /// Fails like [`try_new`](Self::try_new), or if a dynamically shaped
+            /// array does not have the rank of this array.
+            pub fn from_ndarray<S>(context: &'c Context<B>, array: &ndarray::ArrayBase<S, #dimension>) -> Result<Self, Error>
+            where
+                S: ndarray::Data<Elem = #elem_typ_name>,
+            {
+                let shape = array.shape();
+                #check_rank
+
+                match array.as_slice() {
+                    Some(data) => Self::try_new(context, data, #(shape[#dim_indices]),*),
+                    None => {
+                        let data = array.iter().copied().collect::<Vec<_>>();
+                        Self::try_new(context, &data, #(shape[#dim_indices]),*)
+                    }
+                }
+            }
+
+            /// Read the arrays values into an `ndarray` array of the same shape.
+            ///
+            /// See [`values_into`](Self::values_into) for how the values are read.
+            pub fn to_ndarray(&self) -> Result<ndarray::Array<#elem_typ_name, #dimension>, Error> {
+                let values = self.to_vec()?;
+
+                let shape = self.shape();
+                Ok(ndarray::Array::from_shape_vec(#to_dimension, values)
+                    .expect("Futhark array shape matches its values"))
+            }
+        }
+    }
+}
+
+fn serde_template(typ: &ArrayType) -> TokenStream {
+    let rank = typ.rank;
+    let struct_name = typ.struct_ident();
+    let struct_name_str = struct_name.to_string();
+    let elem_typ_name = typ.elements_type.ident();
+
+    let dim_indices = (0..rank).collect::<Vec<_>>();
+    let expected_rank = format!("a shape of rank {rank}");
+
+    quote! {
+        impl<B: Backend> serde::Serialize for #struct_name <'_, B> {
+            /// Serializes the array as its `shape` and its flat, row-major `data`,
+            /// as read by [`to_vec`](Self::to_vec).
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeStruct;
+
+                let data = self.to_vec().map_err(serde::ser::Error::custom)?;
+
+                let mut state = serializer.serialize_struct(#struct_name_str, 2)?;
+                state.serialize_field("shape", &self.shape())?;
+                state.serialize_field("data", &data)?;
+                state.end()
+            }
+        }
+
+        impl<'c, B: Backend> #struct_name <'c, B> {
+            /// Returns a [`Seed`] to deserialize an array in the given context.
+            pub fn seed(context: &'c Context<B>) -> Seed<'c, B, Self> {
+                Seed::new(context)
+            }
+        }
+
+        impl<'de, 'c, B: Backend> serde::de::DeserializeSeed<'de> for Seed<'c, B, #struct_name <'c, B>> {
+            type Value = #struct_name <'c, B>;
+
+            fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+                let seed::ArrayData { shape, data } =
+                    <seed::ArrayData<#elem_typ_name> as serde::Deserialize>::deserialize(deserializer)?;
+
+                if shape.len() != #rank {
+                    return Err(serde::de::Error::invalid_length(shape.len(), &#expected_rank));
+                }
+
+                #struct_name::try_new(self.context, &data, #(shape[#dim_indices]),*)
+                    .map_err(serde::de::Error::custom)
+            }
+        }
+    }
+}
+
+fn index_template(typ: &ArrayType) -> TokenStream {
+    let rank = typ.rank;
+    let fn_index_name = typ.fn_index_ident();
+    let elem_typ_name = typ.elements_type.ident();
+    let dim_indices = (0..rank).collect::<Vec<_>>();
+
+    quote! {
+        /// Read a single element without copying the whole array.
+        ///
+        /// The `index` has one entry per dimension and is checked against the arrays `shape`.
+        /// Like [`values_into`](Self::values_into), this waits for the element to be copied.
+        pub fn get(&self, index: [usize; #rank]) -> Result<#elem_typ_name, Error> {
+            let shape = self.shape();
+            if index.iter().zip(shape).any(|(&i, dim)| i >= dim) {
+                return Err(Error::IndexOutOfBounds {
+                    index: index.to_vec(),
+                    shape: shape.to_vec(),
+                });
+            }
+
+            let mut out = Default::default();
+            Error::check(unsafe {
+                B::#fn_index_name(
+                    self.context.inner,
+                    &mut out,
+                    self.inner,
+                    #(index[#dim_indices] as i64),*
+                )
+            })?;
+
+            if !self.context.sync() {
+                return Err(Error::Sync);
+            }
+
+            Ok(out)
+        }
+    }
+}
+
+fn raw_template(typ: &ArrayType) -> TokenStream {
+    let struct_name = typ.struct_ident();
+
+    let dim_params = (0..typ.rank)
+        .map(|i| format_ident!("dim_{i}"))
+        .collect::<Vec<_>>();
+
+    let new_raw_fn = if typ.ops.new_raw {
+        let fn_new_raw_name = typ.fn_new_raw_ident();
+
+        quote! {
+            /// Create a new Futhark array from backend-native memory without copying it.
+            ///
+            /// The type of `data` depends on the backend, see [`Backend::RawMemory`].
+            /// For the `C`, `MultiCore` and `Ispc` backends, it is a plain host pointer.
+            /// The `Test` backend copies `data` instead, so it is only read during this call.
+            ///
+            /// # Safety
+            /// - `data` must refer to memory of the backend used by `context`.
+            /// - It must hold the product of all dimensions many elements in row-major form.
+            /// - It must stay valid and must not be modified as long as the array exists.
+            pub unsafe fn new_raw(context: &'c Context<B>, data: B::RawMemory, #(#dim_params: usize),*) -> Result<Self, Error> {
+                let inner = B::#fn_new_raw_name(
+                    context.inner,
+                    data,
+                    #(i64::try_from(#dim_params).map_err(|_| Error::DimensionOverflow)?),*
+                );
+
+                if inner.is_null() {
+                    return Err(Error::Allocation);
+                }
+
+                Ok(#struct_name { context, inner })
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let values_raw_fn = if typ.ops.values_raw {
+        let fn_values_raw_name = typ.fn_values_raw_ident();
+
+        quote! {
+            /// Returns the backend-native memory of the array without copying it.
+            ///
+            /// The type of the memory depends on the backend, see [`Backend::RawMemory`].
+            /// For the `C`, `MultiCore` and `Ispc` backends, it is a plain host pointer
+            /// to the values in row-major form.
+            ///
+            /// # Safety
+            /// - The memory is owned by the array and is only valid as long as the array exists.
+            /// - It must not be modified, because Futhark arrays are immutable.
+            /// - The values might still be computed asynchronously,
+            ///   so call [`Context::sync`] before reading them.
+            pub unsafe fn values_raw(&self) -> B::RawMemory {
+                B::#fn_values_raw_name(self.context.inner, self.inner)
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    quote! {
+        #new_raw_fn
+        #values_raw_fn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{ArrayOps, ValueType};
+
+    #[test]
+    fn f16_arrays_keep_their_name() {
+        let typ = ArrayType {
+            elements_type: ValueType::f16,
+            rank: 1,
+            ops: ArrayOps::default(),
+        };
+
+        let code = template(&typ, &Options::default()).to_string();
+
+        assert!(code.contains("type Elem = f32 ;"));
+        assert!(code.contains(r#"const ELEM_NAME : & 'static str = "f16" ;"#));
     }
 }
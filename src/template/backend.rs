@@ -2,7 +2,7 @@ use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 
 use crate::{
-    manifest::{ArrayType, EntryPoint, Manifest, Type},
+    manifest::{ArrayType, EntryPoint, Manifest, OpaqueType, Type},
     Target,
 };
 
@@ -29,6 +29,7 @@ pub fn trait_template(manifest: &Manifest) -> TokenStream {
     let type_fns = manifest.types.iter().map(|typ| match typ {
         Type::Value(_) => quote!(),
         Type::Array(array) => trait_array_template(array),
+        Type::Opaque(opaque) => trait_opaque_template(opaque),
     });
 
     let entry_point_fns = manifest.entry_points.iter().map(trait_entry_point_template);
@@ -40,6 +41,12 @@ pub fn trait_template(manifest: &Manifest) -> TokenStream {
         /// but they are not meant to (and cannot) be used directly.
         #[allow(missing_docs)]
         pub trait Backend {
+            /// Backend-native memory as used by `new_raw` and `values_raw`.
+            ///
+            /// This is a host pointer for the `C`, `MultiCore` and `Ispc` backends,
+            /// a `cl_mem` for the `OpenCL` backend and a `CUdeviceptr` for the `Cuda` backend.
+            type RawMemory: Copy;
+
             unsafe fn futhark_context_config_new() -> *mut types::futhark_context_config;
             unsafe fn futhark_context_config_free(cfg: *mut types::futhark_context_config);
 
@@ -69,7 +76,50 @@ fn trait_array_template(array: &ArrayType) -> TokenStream {
     let name_values = array.fn_values_ident();
     let name_free = array.fn_free_ident();
 
+    let index_fn = if array.ops.index {
+        let name_index = array.fn_index_ident();
+        let params_index = (0..array.rank).map(|i| {
+            let ident = format_ident!("i{}", i);
+
+            quote!(#ident: i64)
+        });
+
+        quote! {
+            unsafe fn #name_index(ctx: *mut types::futhark_context, out: *mut #elem_type_name, array: *mut types::#type_name, #(#params_index),*) -> std::os::raw::c_int;
+        }
+    } else {
+        quote!()
+    };
+
+    let new_raw_fn = if array.ops.new_raw {
+        let name_new_raw = array.fn_new_raw_ident();
+        let params_new_raw = (0..array.rank).map(|i| {
+            let ident = format_ident!("dim_{}", i);
+
+            quote!(#ident: i64)
+        });
+
+        quote! {
+            unsafe fn #name_new_raw(ctx: *mut types::futhark_context, data: Self::RawMemory, #(#params_new_raw),*) -> *mut types::#type_name;
+        }
+    } else {
+        quote!()
+    };
+
+    let values_raw_fn = if array.ops.values_raw {
+        let name_values_raw = array.fn_values_raw_ident();
+
+        quote! {
+            unsafe fn #name_values_raw(ctx: *mut types::futhark_context, array: *mut types::#type_name) -> Self::RawMemory;
+        }
+    } else {
+        quote!()
+    };
+
     quote! {
+        #index_fn
+        #new_raw_fn
+        #values_raw_fn
         unsafe fn #name_new(ctx: *mut types::futhark_context, data: *const #elem_type_name, #(#params_new),*) -> *mut types::#type_name;
         unsafe fn #name_shape(ctx: *mut types::futhark_context, array: *mut types::#type_name) -> *const i64;
         unsafe fn #name_values(ctx: *mut types::futhark_context, array: *mut types::#type_name, data: *mut #elem_type_name) -> std::os::raw::c_int;
@@ -77,13 +127,27 @@ fn trait_array_template(array: &ArrayType) -> TokenStream {
     }
 }
 
+fn trait_opaque_template(opaque: &OpaqueType) -> TokenStream {
+    let type_name = opaque.type_ident();
+    let name_store = opaque.fn_store_ident();
+    let name_restore = opaque.fn_restore_ident();
+    let name_free = opaque.fn_free_ident();
+
+    quote! {
+        unsafe fn #name_store(ctx: *mut types::futhark_context, obj: *const types::#type_name, data: *mut *mut std::os::raw::c_void, size: *mut usize) -> std::os::raw::c_int;
+        unsafe fn #name_restore(ctx: *mut types::futhark_context, data: *const std::os::raw::c_void) -> *mut types::#type_name;
+        unsafe fn #name_free(ctx: *mut types::futhark_context, obj: *mut types::#type_name) -> std::os::raw::c_int;
+    }
+}
+
 fn trait_entry_point_template(ep: &EntryPoint) -> TokenStream {
     let entry_name = ep.futhark_fn_ident();
 
-    let inputs = ep.inputs.iter().enumerate().map(|(i, typ)| {
-        let input_name = format_ident!("in_{}", i);
-
-        match typ {
+    let inputs = ep
+        .inputs
+        .iter()
+        .zip(ep.input_idents())
+        .map(|(input, input_name)| match &input.typ {
             Type::Value(value) => {
                 let type_name = value.ident();
                 quote!(#input_name: #type_name)
@@ -92,13 +156,17 @@ fn trait_entry_point_template(ep: &EntryPoint) -> TokenStream {
                 let type_name = array.type_ident();
                 quote!(#input_name: *const types::#type_name)
             }
-        }
-    });
-
-    let outputs = ep.outputs.iter().enumerate().map(|(i, typ)| {
-        let output_name = format_ident!("out_{}", i);
+            Type::Opaque(opaque) => {
+                let type_name = opaque.type_ident();
+                quote!(#input_name: *const types::#type_name)
+            }
+        });
 
-        match typ {
+    let outputs = ep
+        .outputs
+        .iter()
+        .zip(ep.output_idents())
+        .map(|(output, output_name)| match &output.typ {
             Type::Value(value) => {
                 let type_name = value.ident();
                 quote!(#output_name: *mut #type_name)
@@ -107,8 +175,11 @@ fn trait_entry_point_template(ep: &EntryPoint) -> TokenStream {
                 let type_name = array.type_ident();
                 quote!(#output_name: *mut *mut types::#type_name)
             }
-        }
-    });
+            Type::Opaque(opaque) => {
+                let type_name = opaque.type_ident();
+                quote!(#output_name: *mut *mut types::#type_name)
+            }
+        });
 
     quote! {
         unsafe fn #entry_name (ctx: *mut types::futhark_context, #(#outputs),*, #(#inputs),*) -> std::ffi::c_int;
@@ -121,11 +192,13 @@ pub fn impl_template(manifest: &Manifest, backend: Target) -> TokenStream {
     let type_impls = manifest.types.iter().map(|typ| match typ {
         Type::Value(_) => quote!(),
         Type::Array(array) => impl_array_template(array),
+        Type::Opaque(opaque) => impl_opaque_template(opaque),
     });
 
     let entry_impls = manifest.entry_points.iter().map(impl_entry_point_template);
 
     let sys = sys_template(backend);
+    let raw_memory = raw_memory_template(backend);
 
     let summary_doc = format!("{backend_struct} backend.");
 
@@ -141,6 +214,8 @@ pub fn impl_template(manifest: &Manifest, backend: Target) -> TokenStream {
         }
 
         impl Backend for #backend_struct {
+            type RawMemory = #raw_memory;
+
             unsafe fn futhark_context_config_new() -> *mut types::futhark_context_config {
                 sys::futhark_context_config_new() as *mut types::futhark_context_config
             }
@@ -173,13 +248,25 @@ pub fn impl_template(manifest: &Manifest, backend: Target) -> TokenStream {
     }
 }
 
+fn raw_memory_template(backend: Target) -> TokenStream {
+    match backend {
+        Target::C | Target::MultiCore | Target::ISPC => quote!(*mut u8),
+        Target::OpenCL => quote!(*mut std::ffi::c_void),
+        Target::Cuda => quote!(u64),
+    }
+}
+
 fn impl_entry_point_template(ep: &EntryPoint) -> TokenStream {
     let entry_name = ep.futhark_fn_ident();
 
-    let rust_inputs = ep.inputs.iter().enumerate().map(|(i, typ)| {
-        let input_name = format_ident!("in_{}", i);
+    let input_names = ep.input_idents();
+    let output_names = ep.output_idents();
 
-        match typ {
+    let rust_inputs = ep
+        .inputs
+        .iter()
+        .zip(&input_names)
+        .map(|(input, input_name)| match &input.typ {
             Type::Value(value) => {
                 let type_name = value.ident();
                 quote!(#input_name: #type_name)
@@ -188,13 +275,17 @@ fn impl_entry_point_template(ep: &EntryPoint) -> TokenStream {
                 let type_name = array.type_ident();
                 quote!(#input_name: *const types::#type_name)
             }
-        }
-    });
-
-    let rust_outputs = ep.outputs.iter().enumerate().map(|(i, typ)| {
-        let output_name = format_ident!("out_{}", i);
+            Type::Opaque(opaque) => {
+                let type_name = opaque.type_ident();
+                quote!(#input_name: *const types::#type_name)
+            }
+        });
 
-        match typ {
+    let rust_outputs = ep
+        .outputs
+        .iter()
+        .zip(&output_names)
+        .map(|(output, output_name)| match &output.typ {
             Type::Value(value) => {
                 let type_name = value.ident();
                 quote!(#output_name: *mut #type_name)
@@ -203,13 +294,17 @@ fn impl_entry_point_template(ep: &EntryPoint) -> TokenStream {
                 let type_name = array.type_ident();
                 quote!(#output_name: *mut *mut types::#type_name)
             }
-        }
-    });
-
-    let futhark_inputs = ep.inputs.iter().enumerate().map(|(i, typ)| {
-        let input_name = format_ident!("in_{}", i);
+            Type::Opaque(opaque) => {
+                let type_name = opaque.type_ident();
+                quote!(#output_name: *mut *mut types::#type_name)
+            }
+        });
 
-        match typ {
+    let futhark_inputs = ep
+        .inputs
+        .iter()
+        .zip(&input_names)
+        .map(|(input, input_name)| match &input.typ {
             Type::Value(_) => {
                 quote!(#input_name)
             }
@@ -217,13 +312,17 @@ fn impl_entry_point_template(ep: &EntryPoint) -> TokenStream {
                 let type_name = array.type_ident();
                 quote!(#input_name as *const sys::#type_name)
             }
-        }
-    });
-
-    let futhark_outputs = ep.outputs.iter().enumerate().map(|(i, typ)| {
-        let output_name = format_ident!("out_{}", i);
+            Type::Opaque(opaque) => {
+                let type_name = opaque.type_ident();
+                quote!(#input_name as *const sys::#type_name)
+            }
+        });
 
-        match typ {
+    let futhark_outputs = ep
+        .outputs
+        .iter()
+        .zip(&output_names)
+        .map(|(output, output_name)| match &output.typ {
             Type::Value(_) => {
                 quote!(#output_name)
             }
@@ -231,8 +330,11 @@ fn impl_entry_point_template(ep: &EntryPoint) -> TokenStream {
                 let type_name = array.type_ident();
                 quote!(#output_name as *mut *mut sys::#type_name)
             }
-        }
-    });
+            Type::Opaque(opaque) => {
+                let type_name = opaque.type_ident();
+                quote!(#output_name as *mut *mut sys::#type_name)
+            }
+        });
 
     quote! {
         unsafe fn #entry_name (ctx: *mut types::futhark_context, #(#rust_outputs),*, #(#rust_inputs),*) -> std::ffi::c_int {
@@ -257,7 +359,70 @@ fn impl_array_template(array: &ArrayType) -> TokenStream {
         .map(|i| format_ident!("dim_{}", i))
         .collect::<Vec<_>>();
 
+    let index_fn = if array.ops.index {
+        let name_index = array.fn_index_ident();
+        let indices = (0..array.rank)
+            .map(|i| format_ident!("i{}", i))
+            .collect::<Vec<_>>();
+
+        quote! {
+            unsafe fn #name_index(
+                ctx: *mut types::futhark_context,
+                out: *mut #name_elem,
+                arr: *mut types::#name_type,
+                #(#indices: i64),*
+            ) -> std::os::raw::c_int {
+                sys::#name_index(
+                    ctx as *mut sys::futhark_context,
+                    out,
+                    arr as *mut sys::#name_type,
+                    #(#indices),*
+                )
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let new_raw_fn = if array.ops.new_raw {
+        let name_new_raw = array.fn_new_raw_ident();
+
+        quote! {
+            unsafe fn #name_new_raw(
+                ctx: *mut types::futhark_context,
+                data: Self::RawMemory,
+                #(#dims_new: i64),*
+            ) -> *mut types::#name_type {
+                sys::#name_new_raw(ctx as *mut sys::futhark_context, data as _, #(#dims_new),*) as *mut types::#name_type
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let values_raw_fn = if array.ops.values_raw {
+        let name_values_raw = array.fn_values_raw_ident();
+
+        quote! {
+            unsafe fn #name_values_raw(
+                ctx: *mut types::futhark_context,
+                arr: *mut types::#name_type,
+            ) -> Self::RawMemory {
+                sys::#name_values_raw(
+                    ctx as *mut sys::futhark_context,
+                    arr as *mut sys::#name_type,
+                ) as _
+            }
+        }
+    } else {
+        quote!()
+    };
+
     quote! {
+        #index_fn
+        #new_raw_fn
+        #values_raw_fn
+
         unsafe fn #name_new(
             ctx: *mut types::futhark_context,
             data: *const #name_elem,
@@ -299,3 +464,43 @@ fn impl_array_template(array: &ArrayType) -> TokenStream {
         }
     }
 }
+
+fn impl_opaque_template(opaque: &OpaqueType) -> TokenStream {
+    let name_type = opaque.type_ident();
+    let name_store = opaque.fn_store_ident();
+    let name_restore = opaque.fn_restore_ident();
+    let name_free = opaque.fn_free_ident();
+
+    quote! {
+        unsafe fn #name_store(
+            ctx: *mut types::futhark_context,
+            obj: *const types::#name_type,
+            data: *mut *mut std::os::raw::c_void,
+            size: *mut usize,
+        ) -> std::os::raw::c_int {
+            sys::#name_store(
+                ctx as *mut sys::futhark_context,
+                obj as *const sys::#name_type,
+                data,
+                size as _,
+            )
+        }
+
+        unsafe fn #name_restore(
+            ctx: *mut types::futhark_context,
+            data: *const std::os::raw::c_void,
+        ) -> *mut types::#name_type {
+            sys::#name_restore(ctx as *mut sys::futhark_context, data) as *mut types::#name_type
+        }
+
+        unsafe fn #name_free(
+            ctx: *mut types::futhark_context,
+            obj: *mut types::#name_type,
+        ) -> std::os::raw::c_int {
+            sys::#name_free(
+                ctx as *mut sys::futhark_context,
+                obj as *mut sys::#name_type,
+            )
+        }
+    }
+}
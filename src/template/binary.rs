@@ -0,0 +1,305 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::manifest::ValueType;
+
+pub fn template() -> TokenStream {
+    let element_impls = ValueType::variants()
+        .iter()
+        .filter(|typ| !matches!(typ, ValueType::f16))
+        .map(element_impl_template);
+
+    quote! {
+        /// Futhark's binary value format.
+        ///
+        /// This is the format written by `futhark dataset -b` and accepted by all Futhark tools.
+        /// Each value consists of a small header describing its type and shape,
+        /// followed by its elements in little-endian byte order.
+        ///
+        /// Generated arrays can be read using their `read_binary` constructor
+        /// and written using their `write_binary` method.
+        /// Scalars can be read and written with [`binary::read_scalar`] and [`binary::write_scalar`].
+        pub mod binary {
+            use std::io::{self, Read, Write};
+
+            const MAGIC: u8 = b'b';
+            const VERSION: u8 = 2;
+
+            /// Error returned when a value in Futhark's binary format can not be read or written.
+            #[derive(Debug)]
+            pub enum Error {
+                /// The underlying reader or writer failed.
+                Io(io::Error),
+                /// The data is not a valid value of the expected type.
+                Invalid(String),
+                /// Reading the values of an array failed.
+                Values(super::Error),
+                /// Creating an array from the values failed, like for too large dimensions.
+                Create(super::Error),
+            }
+
+            impl std::fmt::Display for Error {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    match self {
+                        Error::Io(err) => write!(f, "failed to read or write Futhark value: {err}"),
+                        Error::Invalid(message) => write!(f, "invalid Futhark value: {message}"),
+                        Error::Values(err) => write!(f, "failed to read array values: {err}"),
+                        Error::Create(err) => write!(f, "failed to create array: {err}"),
+                    }
+                }
+            }
+
+            impl std::error::Error for Error {
+                fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                    match self {
+                        Error::Io(err) => Some(err),
+                        Error::Invalid(_) => None,
+                        Error::Values(err) | Error::Create(err) => Some(err),
+                    }
+                }
+            }
+
+            impl From<io::Error> for Error {
+                fn from(err: io::Error) -> Self {
+                    Error::Io(err)
+                }
+            }
+
+            /// Scalar types that can be stored in Futhark's binary value format.
+            ///
+            /// Note that `f16` values are represented as `f32` on the Rust side.
+            /// Arrays of `f16` convert their elements automatically,
+            /// but scalars written with [`write_scalar`] always use the `f32` type.
+            pub trait Element: Copy {
+                /// The Futhark type of the element.
+                const TYPE: &'static str;
+
+                /// Encode the element as the Futhark type `typ` in little-endian byte order.
+                fn encode(self, typ: &str, out: &mut Vec<u8>);
+
+                /// Decode an element of the Futhark type `typ` from little-endian bytes.
+                fn decode(typ: &str, bytes: &[u8]) -> Self;
+            }
+
+            #(#element_impls)*
+
+            /// Returns the size in bytes of a single element of the Futhark type `typ`.
+            fn element_size(typ: &str) -> usize {
+                match typ {
+                    "i8" | "u8" => 1,
+                    "i16" | "u16" | "f16" => 2,
+                    "i32" | "u32" | "f32" => 4,
+                    _ => 8,
+                }
+            }
+
+            fn f32_to_f16(value: f32) -> u16 {
+                let bits = value.to_bits();
+                let sign = ((bits >> 16) & 0x8000) as u16;
+                let exponent = ((bits >> 23) & 0xff) as i32;
+                let mantissa = bits & 0x7f_ffff;
+
+                if exponent == 0xff {
+                    let nan = if mantissa != 0 { 0x200 } else { 0 };
+                    return sign | 0x7c00 | nan;
+                }
+
+                let exponent = exponent - 127 + 15;
+                if exponent >= 0x1f {
+                    return sign | 0x7c00;
+                }
+
+                let (half, shift, mantissa) = if exponent <= 0 {
+                    if exponent < -10 {
+                        return sign;
+                    }
+                    let shift = (14 - exponent) as u32;
+                    (0, shift, mantissa | 0x80_0000)
+                } else {
+                    ((exponent as u16) << 10, 13, mantissa)
+                };
+
+                let mut half = half | (mantissa >> shift) as u16;
+                let remainder = mantissa & ((1 << shift) - 1);
+                let midpoint = 1 << (shift - 1);
+                if remainder > midpoint || (remainder == midpoint && half & 1 == 1) {
+                    half += 1;
+                }
+
+                sign | half
+            }
+
+            fn f16_to_f32(half: u16) -> f32 {
+                let sign = ((half & 0x8000) as u32) << 16;
+                let exponent = ((half >> 10) & 0x1f) as u32;
+                let mantissa = (half & 0x3ff) as u32;
+
+                let bits = match (exponent, mantissa) {
+                    (0, 0) => sign,
+                    (0, _) => {
+                        let value = mantissa as f32 * 2f32.powi(-24);
+                        return if sign != 0 { -value } else { value };
+                    }
+                    (0x1f, 0) => sign | 0x7f80_0000,
+                    (0x1f, _) => sign | 0x7fc0_0000 | (mantissa << 13),
+                    _ => sign | ((exponent + 112) << 23) | (mantissa << 13),
+                };
+
+                f32::from_bits(bits)
+            }
+
+            fn write_header(writer: &mut impl Write, typ: &str, shape: &[usize]) -> Result<(), Error> {
+                let mut header = vec![MAGIC, VERSION, shape.len() as u8];
+                header.extend(format!("{typ:>4}").bytes());
+                for &dim in shape {
+                    header.extend((dim as u64).to_le_bytes());
+                }
+
+                writer.write_all(&header)?;
+                Ok(())
+            }
+
+            fn read_header(reader: &mut impl Read, typ: &str, rank: usize) -> Result<Vec<usize>, Error> {
+                let mut byte = [0u8];
+                loop {
+                    reader.read_exact(&mut byte)?;
+                    if !byte[0].is_ascii_whitespace() {
+                        break;
+                    }
+                }
+
+                if byte[0] != MAGIC {
+                    return Err(Error::Invalid("missing binary marker `b`".into()));
+                }
+
+                let mut header = [0u8; 6];
+                reader.read_exact(&mut header)?;
+
+                if header[0] != VERSION {
+                    return Err(Error::Invalid(format!("unsupported version {}", header[0])));
+                }
+                if header[1] as usize != rank {
+                    return Err(Error::Invalid(format!("expected rank {rank}, found rank {}", header[1])));
+                }
+
+                let found = String::from_utf8_lossy(&header[2..]);
+                if found.trim_start() != typ {
+                    return Err(Error::Invalid(format!("expected type {typ}, found type {}", found.trim_start())));
+                }
+
+                let mut shape = Vec::with_capacity(rank);
+                for _ in 0..rank {
+                    let mut dim = [0u8; 8];
+                    reader.read_exact(&mut dim)?;
+                    let dim = usize::try_from(u64::from_le_bytes(dim))
+                        .map_err(|_| Error::Invalid("dimension does not fit into usize".into()))?;
+                    shape.push(dim);
+                }
+
+                Ok(shape)
+            }
+
+            /// Writes an array with the given shape and element type.
+            #[allow(dead_code)]
+            pub(crate) fn write_array<T: Element>(
+                writer: &mut impl Write,
+                values: &[T],
+                shape: &[usize],
+                typ: &str,
+            ) -> Result<(), Error> {
+                write_header(writer, typ, shape)?;
+
+                let mut data = Vec::with_capacity(values.len() * element_size(typ));
+                for &value in values {
+                    value.encode(typ, &mut data);
+                }
+
+                writer.write_all(&data)?;
+                Ok(())
+            }
+
+            /// Reads an array of the given rank and element type and returns its flat values and shape.
+            #[allow(dead_code)]
+            pub(crate) fn read_array<T: Element>(
+                reader: &mut impl Read,
+                rank: usize,
+                typ: &str,
+            ) -> Result<(Vec<T>, Vec<usize>), Error> {
+                let shape = read_header(reader, typ, rank)?;
+
+                let size = element_size(typ);
+                let len = shape
+                    .iter()
+                    .try_fold(size, |acc, &dim| acc.checked_mul(dim))
+                    .ok_or_else(|| Error::Invalid("array is too large".into()))?;
+
+                let mut data = Vec::new();
+                reader.take(len as u64).read_to_end(&mut data)?;
+                if data.len() != len {
+                    return Err(Error::Io(io::ErrorKind::UnexpectedEof.into()));
+                }
+
+                let values = data.chunks_exact(size).map(|bytes| T::decode(typ, bytes)).collect();
+
+                Ok((values, shape))
+            }
+
+            /// Writes a scalar in Futhark's binary value format.
+            pub fn write_scalar<T: Element>(writer: &mut impl Write, value: T) -> Result<(), Error> {
+                write_array(writer, &[value], &[], T::TYPE)
+            }
+
+            /// Reads a scalar in Futhark's binary value format.
+            ///
+            /// Leading whitespace is skipped, so consecutive values of a dataset can be read one after another.
+            pub fn read_scalar<T: Element>(reader: &mut impl Read) -> Result<T, Error> {
+                let (values, _) = read_array(reader, 0, T::TYPE)?;
+                Ok(values[0])
+            }
+        }
+    }
+}
+
+fn element_impl_template(typ: &ValueType) -> TokenStream {
+    let rust_type = typ.ident();
+    let name = typ.name();
+
+    let (typ_param, encode, decode) = match typ {
+        ValueType::f32 => (
+            quote!(typ),
+            quote! {
+                if typ == "f16" {
+                    out.extend(f32_to_f16(self).to_le_bytes());
+                } else {
+                    out.extend(self.to_le_bytes());
+                }
+            },
+            quote! {
+                if typ == "f16" {
+                    f16_to_f32(u16::from_le_bytes(bytes.try_into().unwrap()))
+                } else {
+                    f32::from_le_bytes(bytes.try_into().unwrap())
+                }
+            },
+        ),
+        _ => (
+            quote!(_typ),
+            quote!(out.extend(self.to_le_bytes());),
+            quote!(#rust_type::from_le_bytes(bytes.try_into().unwrap())),
+        ),
+    };
+
+    quote! {
+        impl Element for #rust_type {
+            const TYPE: &'static str = #name;
+
+            fn encode(self, #typ_param: &str, out: &mut Vec<u8>) {
+                #encode
+            }
+
+            fn decode(#typ_param: &str, bytes: &[u8]) -> Self {
+                #decode
+            }
+        }
+    }
+}
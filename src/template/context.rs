@@ -1,10 +1,32 @@
 use proc_macro2::TokenStream;
-use quote::{format_ident, quote};
+use quote::quote;
 
-use crate::manifest::{EntryPoint, Manifest, Type};
+use crate::{
+    manifest::{EntryPoint, Manifest, Type},
+    template::Options,
+};
 
-pub fn template(manifest: &Manifest) -> TokenStream {
-    let entry_fns = manifest.entry_points.iter().map(entry_fn_template);
+pub fn template(manifest: &Manifest, options: &Options) -> TokenStream {
+    let entry_fns = manifest
+        .entry_points
+        .iter()
+        .map(|ep| entry_fn_template(ep, options));
+
+    let output_structs = manifest
+        .entry_points
+        .iter()
+        .filter(|ep| uses_output_struct(ep, options))
+        .map(output_struct_template);
+
+    let trait_entry_fns = manifest
+        .entry_points
+        .iter()
+        .map(|ep| trait_entry_fn_template(ep, options));
+
+    let impl_entry_fns = manifest
+        .entry_points
+        .iter()
+        .map(|ep| impl_entry_fn_template(ep, options));
 
     quote::quote! {
         /// Futhark context object.
@@ -67,47 +89,118 @@ pub fn template(manifest: &Manifest) -> TokenStream {
                 }
             }
         }
+
+        /// Entry points of the Futhark program.
+        ///
+        /// This is implemented by [`Context`], which runs the Futhark code.
+        /// Code that only calls entry points can be generic over this trait,
+        /// so tests can substitute a hand-written implementation.
+        /// Such an implementation can hold a `Context` of the test backend,
+        /// to create the arrays and opaque values it returns without running Futhark.
+        pub trait EntryPoints<B: Backend> {
+            #(#trait_entry_fns)*
+        }
+
+        impl<B: Backend> EntryPoints<B> for Context<B> {
+            #(#impl_entry_fns)*
+        }
+
+        #(#output_structs)*
     }
 }
 
-fn entry_fn_template(ep: &EntryPoint) -> TokenStream {
-    let futhark_entry_name = ep.futhark_fn_ident();
+/// Returns the `Context` method of `ep`, and its output struct, on their own.
+pub fn entry_point_template(ep: &EntryPoint, options: &Options) -> TokenStream {
+    let entry_fn = entry_fn_template(ep, options);
+    let output_struct = if uses_output_struct(ep, options) {
+        output_struct_template(ep)
+    } else {
+        quote!()
+    };
+
+    quote! {
+        impl<B: Backend> Context<B> {
+            #entry_fn
+        }
+
+        #output_struct
+    }
+}
+
+fn uses_output_struct(ep: &EntryPoint, options: &Options) -> bool {
+    options.output_structs && ep.outputs.len() > 1
+}
+
+fn has_context_output(ep: &EntryPoint) -> bool {
+    ep.outputs
+        .iter()
+        .any(|output| matches!(output.typ, Type::Array(_) | Type::Opaque(_)))
+}
+
+fn output_struct_template(ep: &EntryPoint) -> TokenStream {
+    let struct_name = ep.output_struct_ident();
     let entry_name = ep.context_fn_ident();
+    let field_names = ep.output_idents();
 
-    let rust_input = ep.inputs.iter().enumerate().map(|(i, input)| {
-        let name = format_ident!("in_{}", i);
+    let fields = ep
+        .outputs
+        .iter()
+        .zip(&field_names)
+        .enumerate()
+        .map(|(i, (output, name))| {
+            let doc = format!("Output `{i}` of [`Context::{entry_name}`].");
+            let typ = match &output.typ {
+                Type::Value(value) => {
+                    let typ = value.ident();
 
-        match input {
-            Type::Value(value) => {
-                let typ = value.ident();
+                    quote!(#typ)
+                }
+                Type::Array(array) => {
+                    let typ = array.struct_ident();
 
-                quote!(#name: #typ)
-            }
-            Type::Array(array) => {
-                let typ = array.struct_ident();
+                    quote!(#typ<'c, B>)
+                }
+                Type::Opaque(opaque) => {
+                    let typ = opaque.struct_ident();
 
-                quote!(#name: &#typ<B>)
+                    quote!(#typ<'c, B>)
+                }
+            };
+
+            quote! {
+                #[doc = #doc]
+                pub #name: #typ
             }
-        }
-    });
+        });
 
-    let rust_output = ep.outputs.iter().map(|input| match input {
-        Type::Value(value) => {
-            let typ = value.ident();
+    let generics = if has_context_output(ep) {
+        quote!(<'c, B: Backend>)
+    } else {
+        quote!()
+    };
 
-            quote!(#typ)
-        }
-        Type::Array(array) => {
-            let typ = array.struct_ident();
+    let summary_doc = format!("Outputs of [`Context::{entry_name}`].");
 
-            quote!(#typ<B>)
+    quote! {
+        #[doc = #summary_doc]
+        pub struct #struct_name #generics {
+            #(#fields),*
         }
-    });
+    }
+}
+
+fn entry_fn_template(ep: &EntryPoint, options: &Options) -> TokenStream {
+    let futhark_entry_name = ep.futhark_fn_ident();
+    let entry_name = ep.context_fn_ident();
 
-    let let_output_vars = ep.outputs.iter().enumerate().map(|(i, input)| {
-        let name = format_ident!("out_{}", i);
+    let input_names = ep.input_idents();
+    let output_names = ep.output_idents();
 
-        match input {
+    let let_output_vars = ep
+        .outputs
+        .iter()
+        .zip(&output_names)
+        .map(|(output, name)| match &output.typ {
             Type::Value(value) => {
                 let typ = value.ident();
 
@@ -123,36 +216,50 @@ fn entry_fn_template(ep: &EntryPoint) -> TokenStream {
                     };
                 }
             }
-        }
-    });
+            Type::Opaque(opaque) => {
+                let typ = opaque.struct_ident();
+
+                quote! {
+                    let mut #name = #typ {
+                        context: self,
+                        inner: std::ptr::null_mut(),
+                    };
+                }
+            }
+        });
 
     let futhark_output = ep
         .outputs
         .iter()
-        .enumerate()
-        .map(|(i, typ)| (format_ident!("out_{}", i), typ))
-        .map(|(ident, typ)| match typ {
+        .zip(&output_names)
+        .map(|(output, ident)| match &output.typ {
             Type::Value(_) => quote!(#ident),
-            Type::Array(_) => quote!(#ident.inner),
+            Type::Array(_) | Type::Opaque(_) => quote!(#ident.inner),
         });
 
     let futhark_input = ep
         .inputs
         .iter()
-        .enumerate()
-        .map(|(i, typ)| (format_ident!("in_{}", i), typ))
-        .map(|(ident, typ)| match typ {
+        .zip(&input_names)
+        .map(|(input, ident)| match &input.typ {
             Type::Value(_) => quote!(#ident),
-            Type::Array(_) => quote!(#ident.inner),
+            Type::Array(_) | Type::Opaque(_) => quote!(#ident.inner),
         });
 
-    let output_vars = ep
-        .outputs
-        .iter()
-        .enumerate()
-        .map(|(i, _)| format_ident!("out_{}", i));
+    let return_value = if uses_output_struct(ep, options) {
+        let struct_name = ep.output_struct_ident();
+
+        quote!(#struct_name { #(#output_names),* })
+    } else {
+        quote!((#(#output_names),*))
+    };
 
-    let summary_doc = format!("Entry point `{entry_name}`.");
+    let signature = signature_template(ep, options);
+
+    let summary_doc = match &ep.doc {
+        Some(doc) => doc.clone(),
+        None => format!("Entry point `{entry_name}`."),
+    };
 
     quote! {
         #[doc = #summary_doc]
@@ -161,17 +268,104 @@ fn entry_fn_template(ep: &EntryPoint) -> TokenStream {
         /// Execution might happen asynchronously, so you have to call [`Context::sync`]
         /// before using it. See the documentation of [`Context::sync`] for details.
         #[allow(unused_parens, clippy::double_parens)]
-        pub fn #entry_name(&self, #(#rust_input),*) -> Result<(#(#rust_output),*), i64> {
+        pub #signature {
             #(#let_output_vars)*
 
             let status = unsafe {
                 B::#futhark_entry_name(self.inner, #(&mut #futhark_output),*, #(#futhark_input),*)
             };
 
-            match status {
-                0 => Ok((#(#output_vars),*)),
-                err => Err(err.into()),
-            }
+            Error::check(status)?;
+
+            Ok(#return_value)
+        }
+    }
+}
+
+fn trait_entry_fn_template(ep: &EntryPoint, options: &Options) -> TokenStream {
+    let signature = signature_template(ep, options);
+
+    let summary_doc = match &ep.doc {
+        Some(doc) => doc.clone(),
+        None => format!("Entry point `{}`.", ep.context_fn_ident()),
+    };
+
+    quote! {
+        #[doc = #summary_doc]
+        #[allow(unused_parens, clippy::double_parens)]
+        #signature;
+    }
+}
+
+fn impl_entry_fn_template(ep: &EntryPoint, options: &Options) -> TokenStream {
+    let entry_name = ep.context_fn_ident();
+    let input_names = ep.input_idents();
+    let signature = signature_template(ep, options);
+
+    quote! {
+        #[allow(unused_parens, clippy::double_parens)]
+        #signature {
+            Context::#entry_name(self, #(#input_names),*)
         }
     }
 }
+
+fn signature_template(ep: &EntryPoint, options: &Options) -> TokenStream {
+    let entry_name = ep.context_fn_ident();
+    let input_names = ep.input_idents();
+
+    let rust_input = ep
+        .inputs
+        .iter()
+        .zip(&input_names)
+        .map(|(input, name)| match &input.typ {
+            Type::Value(value) => {
+                let typ = value.ident();
+
+                quote!(#name: #typ)
+            }
+            Type::Array(array) => {
+                let typ = array.struct_ident();
+
+                quote!(#name: &#typ<B>)
+            }
+            Type::Opaque(opaque) => {
+                let typ = opaque.struct_ident();
+
+                quote!(#name: &#typ<B>)
+            }
+        });
+
+    let rust_output = ep.outputs.iter().map(|output| match &output.typ {
+        Type::Value(value) => {
+            let typ = value.ident();
+
+            quote!(#typ)
+        }
+        Type::Array(array) => {
+            let typ = array.struct_ident();
+
+            quote!(#typ<B>)
+        }
+        Type::Opaque(opaque) => {
+            let typ = opaque.struct_ident();
+
+            quote!(#typ<B>)
+        }
+    });
+
+    let return_type = if uses_output_struct(ep, options) {
+        let struct_name = ep.output_struct_ident();
+        let generics = if has_context_output(ep) {
+            quote!(<B>)
+        } else {
+            quote!()
+        };
+
+        quote!(#struct_name #generics)
+    } else {
+        quote!((#(#rust_output),*))
+    };
+
+    quote!(fn #entry_name(&self, #(#rust_input),*) -> Result<#return_type, Error>)
+}
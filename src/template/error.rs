@@ -0,0 +1,77 @@
+use proc_macro2::TokenStream;
+
+pub fn template() -> TokenStream {
+    quote::quote! {
+        /// Errors reported when working with Futhark values.
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum Error {
+            /// A Futhark function returned the given non-zero status code.
+            Status(i32),
+            /// Synchronizing the [`Context`] failed, see [`Context::sync`].
+            Sync,
+            /// Futhark failed to allocate a new value.
+            Allocation,
+            /// Futhark failed to restore an opaque value from bytes.
+            Restore,
+            /// An array dimension or the number of elements does not fit into the required integer type.
+            DimensionOverflow,
+            /// An index was outside of the arrays shape.
+            IndexOutOfBounds {
+                /// The requested index.
+                index: Vec<usize>,
+                /// The shape of the array.
+                shape: Vec<usize>,
+            },
+            /// A buffer did not have the length required by an array.
+            LengthMismatch {
+                /// The required length.
+                expected: usize,
+                /// The length of the given buffer.
+                actual: usize,
+            },
+            /// An array did not have the rank required by an array type.
+            RankMismatch {
+                /// The required rank.
+                expected: usize,
+                /// The rank of the given array.
+                actual: usize,
+            },
+        }
+
+        impl Error {
+            #[allow(dead_code)]
+            pub(crate) fn check(status: std::os::raw::c_int) -> Result<(), Error> {
+                match status {
+                    0 => Ok(()),
+                    status => Err(Error::Status(status)),
+                }
+            }
+        }
+
+        impl std::fmt::Display for Error {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    Error::Status(status) => write!(f, "Futhark failed with status code {status}"),
+                    Error::Sync => write!(f, "failed to synchronize the Futhark context"),
+                    Error::Allocation => write!(f, "Futhark failed to allocate a new value"),
+                    Error::Restore => write!(f, "Futhark failed to restore an opaque value"),
+                    Error::DimensionOverflow => write!(f, "array dimensions are too large"),
+                    Error::IndexOutOfBounds { index, shape } => write!(
+                        f,
+                        "index {index:?} is out of bounds for an array of shape {shape:?}"
+                    ),
+                    Error::LengthMismatch { expected, actual } => write!(
+                        f,
+                        "expected a buffer of length {expected}, but got length {actual}"
+                    ),
+                    Error::RankMismatch { expected, actual } => write!(
+                        f,
+                        "expected an array of rank {expected}, but got rank {actual}"
+                    ),
+                }
+            }
+        }
+
+        impl std::error::Error for Error {}
+    }
+}
@@ -0,0 +1,203 @@
+use enumflags2::BitFlags;
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use crate::{
+    manifest::{EntryPoint, Manifest},
+    source::TestCase,
+    template::futhark_tests::{disabled_reason, input_args, input_lets, unsupported_reason},
+    Target,
+};
+
+pub fn template(manifest: &Manifest, targets: BitFlags<Target>) -> TokenStream {
+    let cases = manifest
+        .entry_points
+        .iter()
+        .flat_map(|ep| {
+            ep.tests
+                .iter()
+                .enumerate()
+                .filter(|(_, case)| !case.nobench)
+                .map(move |(i, case)| {
+                    let name = format_ident!("{}_{}", ep.name, i);
+                    (name, i, ep, case, skip_reason(ep, case))
+                })
+        })
+        .collect::<Vec<_>>();
+
+    if cases.is_empty() || targets.is_empty() {
+        return quote!();
+    }
+
+    let case_fns = cases
+        .iter()
+        .filter(|(_, _, _, _, skipped)| skipped.is_none())
+        .map(|(name, dataset, ep, case, _)| case_fn_template(name, *dataset, ep, case));
+
+    let target_mods = targets.iter().map(|target| {
+        let mod_name = format_ident!("{}", target.name());
+        let backend = format_ident!("{}", target.struct_name());
+        let backend_name = target.name();
+
+        let benches = cases
+            .iter()
+            .filter(|(_, _, _, case, _)| disabled_reason(case, target).is_none())
+            .map(|(name, dataset, ep, _, skipped)| {
+                let body = match skipped {
+                    Some(reason) => {
+                        let entry_name = &ep.name;
+                        quote!(super::skip(#backend_name, #entry_name, #dataset, #reason);)
+                    }
+                    None => quote!(super::#name::<super::backends::#backend>(#backend_name);),
+                };
+
+                quote! {
+                    #[test]
+                    #[ignore = "benchmark, run it with `cargo futhark bench`"]
+                    fn #name() {
+                        #body
+                    }
+                }
+            });
+
+        quote! {
+            mod #mod_name {
+                #(#benches)*
+            }
+        }
+    });
+
+    quote! {
+        /// Benchmarks of the `-- ==` blocks in the Futhark source.
+        ///
+        /// They are run by `cargo futhark bench`, which collects and reports the timings.
+        #[cfg(test)]
+        #[allow(dead_code, unused_imports)]
+        mod futhark_bench {
+            use super::*;
+
+            fn parse_scalar<T: textual::Scalar>(index: usize, value: &str, suffix: &str) -> T {
+                textual::parse_scalar(value.trim(), suffix)
+                    .unwrap_or_else(|err| panic!("invalid value {index}: {err}"))
+            }
+
+            fn env_usize(name: &str, default: usize) -> usize {
+                std::env::var(name)
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(default)
+            }
+
+            /// Quotes `value` as JSON string, escaping all control characters.
+            fn json_string(value: &str) -> String {
+                let mut quoted = String::from("\"");
+                for c in value.chars() {
+                    match c {
+                        '"' => quoted.push_str("\\\""),
+                        '\\' => quoted.push_str("\\\\"),
+                        c if u32::from(c) < 0x20 => quoted.push_str(&format!("\\u{:04x}", u32::from(c))),
+                        c => quoted.push(c),
+                    }
+                }
+                quoted.push('"');
+                quoted
+            }
+
+            /// Times `run` and records the runtimes.
+            ///
+            /// If `run` fails, the error is recorded instead of the runtimes.
+            fn measure(backend: &str, entry: &str, dataset: usize, mut run: impl FnMut() -> Result<(), String>) {
+                let warmup = env_usize("CARGO_FUTHARK_BENCH_WARMUP", 1);
+                let runs = env_usize("CARGO_FUTHARK_BENCH_RUNS", 10).max(1);
+
+                let result = (0..warmup)
+                    .try_for_each(|_| run())
+                    .and_then(|()| {
+                        (0..runs)
+                            .map(|_| {
+                                let start = std::time::Instant::now();
+                                run()?;
+                                Ok(format!("{:.3}", start.elapsed().as_secs_f64() * 1e6))
+                            })
+                            .collect::<Result<Vec<_>, String>>()
+                    });
+
+                let outcome = match result {
+                    Ok(runtimes) => format!("\"runtimes_us\":[{}]", runtimes.join(",")),
+                    Err(err) => format!("\"error\":{}", json_string(&err)),
+                };
+
+                record(backend, entry, dataset, &outcome);
+            }
+
+            /// Records that the dataset can not be benchmarked through the bindings.
+            fn skip(backend: &str, entry: &str, dataset: usize, reason: &str) {
+                record(backend, entry, dataset, &format!("\"skipped\":{}", json_string(reason)));
+            }
+
+            /// Appends a JSON line ending in the `outcome` field to `CARGO_FUTHARK_BENCH_OUTPUT`.
+            fn record(backend: &str, entry: &str, dataset: usize, outcome: &str) {
+                let Ok(path) = std::env::var("CARGO_FUTHARK_BENCH_OUTPUT") else {
+                    return;
+                };
+                let line = format!(
+                    "{{\"backend\":{},\"entry\":{},\"dataset\":{dataset},{outcome}}}\n",
+                    json_string(backend),
+                    json_string(entry),
+                );
+
+                use std::io::Write;
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .and_then(|mut file| file.write_all(line.as_bytes()))
+                    .expect("failed to write benchmark results");
+            }
+
+            #(#case_fns)*
+
+            #(#target_mods)*
+        }
+    }
+}
+
+/// Reason why `case` can not be benchmarked, `None` if it can.
+fn skip_reason(ep: &EntryPoint, case: &TestCase) -> Option<String> {
+    if let Some(reason) = unsupported_reason(ep, case) {
+        return Some(reason.to_string());
+    }
+
+    (case.inputs.len() != ep.inputs.len()).then(|| {
+        format!(
+            "`{}` takes {} inputs, but the dataset has {}",
+            ep.name,
+            ep.inputs.len(),
+            case.inputs.len()
+        )
+    })
+}
+
+fn case_fn_template(name: &Ident, dataset: usize, ep: &EntryPoint, case: &TestCase) -> TokenStream {
+    let entry_name = &ep.name;
+    let entry_fn = ep.context_fn_ident();
+    let let_inputs = input_lets(ep, case);
+    let args = input_args(ep);
+
+    quote! {
+        fn #name<B: Backend>(backend: &str) {
+            let context = Context::new(Config::<B>::new());
+
+            #(#let_inputs)*
+
+            measure(backend, #entry_name, #dataset, || {
+                context.#entry_fn(#(#args),*).map_err(|err| err.to_string())?;
+                if context.sync() {
+                    Ok(())
+                } else {
+                    Err("failed to sync the context".to_string())
+                }
+            });
+        }
+    }
+}
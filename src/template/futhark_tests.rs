@@ -0,0 +1,341 @@
+use enumflags2::BitFlags;
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::{format_ident, quote};
+
+use crate::{
+    manifest::{EntryPoint, Manifest, Type, ValueType},
+    source::{Outcome, TestCase},
+    template::Options,
+    Target,
+};
+
+pub fn template(manifest: &Manifest, targets: BitFlags<Target>, options: &Options) -> TokenStream {
+    let cases = manifest
+        .entry_points
+        .iter()
+        .flat_map(|ep| {
+            ep.tests
+                .iter()
+                .enumerate()
+                .map(move |(i, case)| (format_ident!("{}_{}", ep.name, i), ep, case))
+        })
+        .collect::<Vec<_>>();
+
+    if cases.is_empty() || targets.is_empty() {
+        return quote!();
+    }
+
+    let approx_impls = ValueType::variants()
+        .iter()
+        .filter(|typ| !matches!(typ, ValueType::f16))
+        .map(approx_impl_template);
+
+    let case_fns = cases
+        .iter()
+        .filter(|(_, ep, case)| unsupported_reason(ep, case).is_none())
+        .map(|(name, ep, case)| case_fn_template(name, ep, case, options));
+
+    let target_mods = targets.iter().map(|target| {
+        let mod_name = format_ident!("{}", target.name());
+        let backend = format_ident!("{}", target.struct_name());
+
+        let tests = cases.iter().map(|(name, ep, case)| {
+            if let Some(reason) = unsupported_reason(ep, case) {
+                return quote! {
+                    #[test]
+                    #[ignore = #reason]
+                    fn #name() {}
+                };
+            }
+
+            let ignore = disabled_reason(case, target).map(|reason| quote!(#[ignore = #reason]));
+
+            quote! {
+                #[test]
+                #ignore
+                fn #name() {
+                    super::#name::<super::backends::#backend>();
+                }
+            }
+        });
+
+        quote! {
+            mod #mod_name {
+                #(#tests)*
+            }
+        }
+    });
+
+    quote! {
+        /// Test cases of the `-- ==` blocks in the Futhark source.
+        ///
+        /// They are run for every backend by `cargo futhark test`, or `cargo test futhark_tests`.
+        #[cfg(test)]
+        #[allow(dead_code, unused_imports)]
+        mod futhark_tests {
+            use super::*;
+
+            /// Comparison of results, with a relative tolerance for floating point numbers.
+            trait Approx: Copy {
+                fn approx_eq(self, expected: Self) -> bool;
+            }
+
+            #(#approx_impls)*
+
+            fn parse_scalar<T: textual::Scalar>(index: usize, value: &str, suffix: &str) -> T {
+                textual::parse_scalar(value.trim(), suffix)
+                    .unwrap_or_else(|err| panic!("invalid value {index}: {err}"))
+            }
+
+            fn check_scalar<T: Approx + textual::Scalar + std::fmt::Debug>(
+                index: usize,
+                actual: T,
+                expected: &str,
+                suffix: &str,
+            ) {
+                let expected = parse_scalar::<T>(index, expected, suffix);
+                assert!(
+                    actual.approx_eq(expected),
+                    "output {index}: expected {expected:?}, got {actual:?}"
+                );
+            }
+
+            fn check_array<'c, B: Backend, A>(index: usize, actual: &A, expected: &A)
+            where
+                A: FutharkArray<'c, B> + std::fmt::Display,
+                A::Elem: Approx,
+            {
+                assert_eq!(
+                    actual.shape().as_ref(),
+                    expected.shape().as_ref(),
+                    "output {index} has the wrong shape"
+                );
+
+                let actual_values = actual.to_vec().expect("failed to read output");
+                let expected_values = expected.to_vec().expect("failed to read expected output");
+                assert!(
+                    actual_values
+                        .iter()
+                        .zip(&expected_values)
+                        .all(|(actual, expected)| actual.approx_eq(*expected)),
+                    "output {index}: expected {expected}, got {actual}"
+                );
+            }
+
+            #(#case_fns)*
+
+            #(#target_mods)*
+        }
+    }
+}
+
+fn approx_impl_template(typ: &ValueType) -> TokenStream {
+    let ident = typ.ident();
+
+    let body = if typ.is_float() {
+        quote! {
+            if self.is_nan() || expected.is_nan() {
+                return self.is_nan() && expected.is_nan();
+            }
+            if self.is_infinite() || expected.is_infinite() {
+                return self == expected;
+            }
+
+            (self - expected).abs() <= 0.002 * expected.abs().max(1.0)
+        }
+    } else {
+        quote!(self == expected)
+    };
+
+    quote! {
+        impl Approx for #ident {
+            fn approx_eq(self, expected: Self) -> bool {
+                #body
+            }
+        }
+    }
+}
+
+pub(super) fn unsupported_reason(ep: &EntryPoint, case: &TestCase) -> Option<&'static str> {
+    let uses_opaque = ep
+        .inputs
+        .iter()
+        .map(|input| &input.typ)
+        .chain(ep.outputs.iter().map(|output| &output.typ))
+        .any(|typ| matches!(typ, Type::Opaque(_)));
+
+    case.unsupported
+        .or(uses_opaque.then_some("opaque values are not supported"))
+}
+
+pub(super) fn disabled_reason(case: &TestCase, target: Target) -> Option<&'static str> {
+    let backend_tag = format!("no_{}", target.name());
+
+    if case.tags.iter().any(|tag| tag == "disable") {
+        Some("disabled by the `disable` tag")
+    } else if case.tags.contains(&backend_tag) {
+        Some("disabled for this backend by a `no_` tag")
+    } else {
+        None
+    }
+}
+
+fn case_fn_template(
+    name: &Ident,
+    ep: &EntryPoint,
+    case: &TestCase,
+    options: &Options,
+) -> TokenStream {
+    let entry_fn = ep.context_fn_ident();
+
+    if case.inputs.len() != ep.inputs.len() {
+        let message = format!(
+            "`{}` takes {} inputs, but the test has {}",
+            ep.name,
+            ep.inputs.len(),
+            case.inputs.len()
+        );
+
+        return quote! {
+            fn #name<B: Backend>() {
+                panic!(#message);
+            }
+        };
+    }
+
+    let let_inputs = input_lets(ep, case);
+    let args = input_args(ep);
+
+    let check_outcome = match &case.outcome {
+        Outcome::Success => quote! {
+            if let Err(err) = result {
+                panic!("entry point failed: {err}");
+            }
+            assert!(context.sync(), "failed to sync the context");
+        },
+        Outcome::Error => quote! {
+            assert!(
+                result.is_err() || !context.sync(),
+                "entry point succeeded, but an error was expected"
+            );
+        },
+        Outcome::Values(expected) if expected.len() != ep.outputs.len() => {
+            let message = format!(
+                "`{}` returns {} outputs, but the test expects {}",
+                ep.name,
+                ep.outputs.len(),
+                expected.len()
+            );
+
+            quote!(panic!(#message);)
+        }
+        Outcome::Values(expected) => {
+            let checks = ep.outputs.iter().zip(expected).enumerate().map(
+                |(i, (output, expected))| {
+                    let actual = output_accessor(ep, i, options);
+
+                    match &output.typ {
+                        Type::Value(typ) => {
+                            let suffix = typ.name();
+
+                            quote!(check_scalar(#i, #actual, #expected, #suffix);)
+                        }
+                        Type::Array(array) => {
+                            let ident = array.struct_ident();
+
+                            quote! {
+                                let expected = #ident::from_textual(&context, #expected)
+                                    .unwrap_or_else(|err| panic!("invalid output {}: {err}", #i));
+                                check_array(#i, &#actual, &expected);
+                            }
+                        }
+                        Type::Opaque(_) => unreachable!("opaque outputs are not supported"),
+                    }
+                },
+            );
+
+            quote! {
+                let outputs = result
+                    .unwrap_or_else(|err| panic!("entry point failed: {err}"));
+                assert!(context.sync(), "failed to sync the context");
+
+                #(#checks)*
+            }
+        }
+    };
+
+    quote! {
+        fn #name<B: Backend>() {
+            let context = Context::new(Config::<B>::new());
+
+            #(#let_inputs)*
+
+            let result = context.#entry_fn(#(#args),*);
+
+            #check_outcome
+        }
+    }
+}
+
+/// Returns a `let input_{i} = ...;` statement per input of `case`.
+///
+/// The inputs are parsed from Futhark's textual value format using a `parse_scalar`
+/// function and a `context` variable in scope.
+pub(super) fn input_lets(ep: &EntryPoint, case: &TestCase) -> Vec<TokenStream> {
+    ep.inputs
+        .iter()
+        .zip(&case.inputs)
+        .enumerate()
+        .map(|(i, (input, value))| {
+            let var = format_ident!("input_{}", i);
+
+            match &input.typ {
+                Type::Value(typ) => {
+                    let ident = typ.ident();
+                    let suffix = typ.name();
+
+                    quote!(let #var = parse_scalar::<#ident>(#i, #value, #suffix);)
+                }
+                Type::Array(array) => {
+                    let ident = array.struct_ident();
+
+                    quote! {
+                        let #var = #ident::from_textual(&context, #value)
+                            .unwrap_or_else(|err| panic!("invalid input {}: {err}", #i));
+                    }
+                }
+                Type::Opaque(_) => unreachable!("opaque inputs are not supported"),
+            }
+        })
+        .collect()
+}
+
+/// Returns the arguments passing the variables of [`input_lets`] to the entry point.
+pub(super) fn input_args(ep: &EntryPoint) -> Vec<TokenStream> {
+    ep.inputs
+        .iter()
+        .enumerate()
+        .map(|(i, input)| {
+            let var = format_ident!("input_{}", i);
+
+            match &input.typ {
+                Type::Value(_) => quote!(#var),
+                Type::Array(_) | Type::Opaque(_) => quote!(&#var),
+            }
+        })
+        .collect()
+}
+
+fn output_accessor(ep: &EntryPoint, index: usize, options: &Options) -> TokenStream {
+    if ep.outputs.len() == 1 {
+        quote!(outputs)
+    } else if options.output_structs {
+        let field = &ep.output_idents()[index];
+
+        quote!(outputs.#field)
+    } else {
+        let index = Literal::usize_unsuffixed(index);
+
+        quote!(outputs.#index)
+    }
+}
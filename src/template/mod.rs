@@ -8,21 +8,63 @@ use quote::{format_ident, quote};
 
 mod array;
 mod backend;
+mod binary;
 mod config;
 mod context;
+mod error;
+mod futhark_bench;
+mod futhark_tests;
+mod opaque;
+mod seed;
+mod test_backend;
+mod textual;
+mod traits;
 mod types;
 
-pub fn combined(manifest: &Manifest, targets: BitFlags<Target>) -> TokenStream {
+/// Options that change the shape of the generated code.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    /// Return a struct with positional fields from entry points with multiple outputs.
+    pub output_structs: bool,
+    /// Generate conversions between arrays and the `ndarray` crate.
+    pub ndarray: bool,
+    /// Generate `serde` support for arrays and opaque values.
+    pub serde: bool,
+    /// Generate the pure-Rust `Test` backend.
+    pub test_backend: bool,
+}
+
+pub fn combined(manifest: &Manifest, targets: BitFlags<Target>, options: &Options) -> TokenStream {
     let config = config::template();
-    let context = context::template(manifest);
+    let context = context::template(manifest, options);
     let types = types::template(manifest);
+    let error = error::template();
+    let textual = textual::template(manifest);
+    let binary = binary::template();
+    let traits = traits::template();
     let backend_trait = backend::trait_template(manifest);
 
     let structs = manifest.types.iter().map(|typ| match typ {
         Type::Value(_) => quote!(),
-        Type::Array(array) => array::template(array),
+        Type::Array(array) => array::template(array, options),
+        Type::Opaque(opaque) => opaque::template(opaque, options),
     });
 
+    let test_backend = if options.test_backend {
+        test_backend::template(manifest)
+    } else {
+        quote!()
+    };
+
+    let futhark_tests = futhark_tests::template(manifest, targets, options);
+    let futhark_bench = futhark_bench::template(manifest, targets);
+
+    let seed = if options.serde {
+        seed::template()
+    } else {
+        quote!()
+    };
+
     let backends = targets.iter().map(|target| {
         let target_name = format_ident!("{}", target.name());
         let target_struct_name = format_ident!("{}", target.struct_name());
@@ -44,9 +86,16 @@ pub fn combined(manifest: &Manifest, targets: BitFlags<Target>) -> TokenStream {
 
         #config
         #context
+        #error
 
         #types
 
+        #traits
+
+        #textual
+        #binary
+        #seed
+
         /// List of supported backends.
         ///
         /// If your desired backend is not available, you might have to modify your `build.rs`
@@ -58,9 +107,33 @@ pub fn combined(manifest: &Manifest, targets: BitFlags<Target>) -> TokenStream {
             #backend_trait
 
             #(#backends)*
+
+            #test_backend
         }
         use backends::Backend;
 
         #(#structs)*
+
+        #futhark_tests
+        #futhark_bench
+    }
+}
+
+/// Returns the code generated for the entry point or type called `name`.
+///
+/// Types can be named like in Futhark (`[]f32`) or like the generated struct (`Array_F32_1D`).
+pub fn item(manifest: &Manifest, name: &str, options: &Options) -> Option<TokenStream> {
+    if let Some(ep) = manifest.entry_points.iter().find(|ep| ep.name == name) {
+        return Some(context::entry_point_template(ep, options));
     }
+
+    manifest.types.iter().find_map(|typ| match typ {
+        Type::Array(array) if array.name() == name || array.struct_ident() == name => {
+            Some(array::template(array, options))
+        }
+        Type::Opaque(opaque) if opaque.name == name || opaque.struct_ident() == name => {
+            Some(opaque::template(opaque, options))
+        }
+        _ => None,
+    })
 }
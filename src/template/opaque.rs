@@ -0,0 +1,132 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{manifest::OpaqueType, template::Options};
+
+pub fn template(typ: &OpaqueType, options: &Options) -> TokenStream {
+    let struct_name = typ.struct_ident();
+    let type_name = typ.type_ident();
+    let fn_store_name = typ.fn_store_ident();
+    let fn_restore_name = typ.fn_restore_ident();
+    let fn_free_name = typ.fn_free_ident();
+
+    let summary_doc = match &typ.doc {
+        Some(doc) => doc.clone(),
+        None => format!("Opaque Futhark value of type `{}`.", typ.name),
+    };
+
+    let serde_impl = if options.serde {
+        serde_template(typ)
+    } else {
+        quote!()
+    };
+
+    quote! {
+        #[doc = #summary_doc]
+        ///
+        /// # Opaqueness
+        /// The contents of opaque values can only be accessed from Futhark code.
+        /// They can be passed to and returned from `entry` functions
+        /// and be stored as bytes using `to_bytes` and restored with the unsafe `from_bytes`.
+        #[allow(non_camel_case_types)]
+        pub struct #struct_name <'c, B: Backend> {
+            pub(crate) context: &'c Context<B>,
+            pub(crate) inner: *mut types::#type_name,
+        }
+
+        impl<'c, B: Backend> #struct_name <'c, B> {
+            /// Serialize the value into bytes.
+            ///
+            /// The byte representation is stable across backends,
+            /// but not across Futhark compiler versions or changes to the Futhark program.
+            /// This includes a [`Context::sync`].
+            pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+                let mut len = 0;
+                Error::check(unsafe {
+                    B::#fn_store_name(self.context.inner, self.inner, std::ptr::null_mut(), &mut len)
+                })?;
+
+                let mut bytes = vec![0u8; len];
+                let mut data = bytes.as_mut_ptr() as *mut std::os::raw::c_void;
+                Error::check(unsafe {
+                    B::#fn_store_name(self.context.inner, self.inner, &mut data, &mut len)
+                })?;
+
+                if !self.context.sync() {
+                    return Err(Error::Sync);
+                }
+
+                Ok(bytes)
+            }
+
+            /// Restore a value from bytes created by [`to_bytes`](Self::to_bytes).
+            ///
+            /// The bytes may have been created with another backend.
+            ///
+            /// # Safety
+            /// Futhark does not get the length of `bytes`, it reads as many bytes as the headers
+            /// stored inside announce. Truncated or modified bytes make it read out of bounds, so:
+            /// - `bytes` must have been created by `to_bytes` of this type, unchanged.
+            /// - They must come from the same Futhark program and compiler version.
+            pub unsafe fn from_bytes(context: &'c Context<B>, bytes: &[u8]) -> Result<Self, Error> {
+                let inner = unsafe {
+                    B::#fn_restore_name(context.inner, bytes.as_ptr() as *const std::os::raw::c_void)
+                };
+
+                if inner.is_null() {
+                    return Err(Error::Restore);
+                }
+
+                Ok(#struct_name { context, inner })
+            }
+        }
+
+        impl<B: Backend> Drop for #struct_name <'_, B> {
+            fn drop(&mut self) {
+                if !self.inner.is_null() {
+                    unsafe {
+                        B::#fn_free_name(self.context.inner, self.inner);
+                    }
+                }
+            }
+        }
+
+        #serde_impl
+    }
+}
+
+fn serde_template(typ: &OpaqueType) -> TokenStream {
+    let struct_name = typ.struct_ident();
+
+    quote! {
+        impl<B: Backend> serde::Serialize for #struct_name <'_, B> {
+            /// Serializes the value as bytes, see [`to_bytes`](Self::to_bytes).
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let bytes = self.to_bytes().map_err(serde::ser::Error::custom)?;
+                serializer.serialize_bytes(&bytes)
+            }
+        }
+
+        impl<'c, B: Backend> #struct_name <'c, B> {
+            /// Returns a [`Seed`] to deserialize a value in the given context.
+            ///
+            /// # Safety
+            /// Values are restored with [`from_bytes`](Self::from_bytes),
+            /// so the seed may only be used on data serialized from this type,
+            /// by the same Futhark program and compiler version.
+            pub unsafe fn seed(context: &'c Context<B>) -> Seed<'c, B, Self> {
+                Seed::new(context)
+            }
+        }
+
+        impl<'de, 'c, B: Backend> serde::de::DeserializeSeed<'de> for Seed<'c, B, #struct_name <'c, B>> {
+            type Value = #struct_name <'c, B>;
+
+            fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+                let bytes = deserializer.deserialize_bytes(seed::BytesVisitor)?;
+                // The caller of `seed` vouched for the data.
+                unsafe { #struct_name::from_bytes(self.context, &bytes) }.map_err(serde::de::Error::custom)
+            }
+        }
+    }
+}
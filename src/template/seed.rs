@@ -0,0 +1,108 @@
+use proc_macro2::TokenStream;
+
+pub fn template() -> TokenStream {
+    quote::quote! {
+        /// Deserializes Futhark values using [`serde`].
+        ///
+        /// Futhark values can only exist within a [`Context`],
+        /// so they can not implement `serde::Deserialize` directly.
+        /// Instead, a `Seed` carries the context and implements [`serde::de::DeserializeSeed`].
+        /// It is created using the `seed` function of the generated types.
+        pub struct Seed<'c, B: Backend, T> {
+            context: &'c Context<B>,
+            _phantom: PhantomData<T>,
+        }
+
+        impl<'c, B: Backend, T> Seed<'c, B, T> {
+            #[allow(dead_code)]
+            fn new(context: &'c Context<B>) -> Self {
+                Seed {
+                    context,
+                    _phantom: PhantomData,
+                }
+            }
+        }
+
+        #[allow(dead_code)]
+        mod seed {
+            pub(crate) struct BytesVisitor;
+
+            impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+                type Value = Vec<u8>;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str("a byte array")
+                }
+
+                fn visit_bytes<E: serde::de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+                    Ok(bytes.to_vec())
+                }
+
+                fn visit_byte_buf<E: serde::de::Error>(self, bytes: Vec<u8>) -> Result<Self::Value, E> {
+                    Ok(bytes)
+                }
+
+                fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                    let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                    while let Some(byte) = seq.next_element()? {
+                        bytes.push(byte);
+                    }
+                    Ok(bytes)
+                }
+            }
+
+            /// Serialized form of an array.
+            pub(crate) struct ArrayData<T> {
+                pub(crate) shape: Vec<usize>,
+                pub(crate) data: Vec<T>,
+            }
+
+            impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for ArrayData<T> {
+                fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    deserializer.deserialize_struct("Array", &["shape", "data"], ArrayDataVisitor(std::marker::PhantomData))
+                }
+            }
+
+            struct ArrayDataVisitor<T>(std::marker::PhantomData<T>);
+
+            impl<'de, T: serde::Deserialize<'de>> serde::de::Visitor<'de> for ArrayDataVisitor<T> {
+                type Value = ArrayData<T>;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str("an array with a shape and data")
+                }
+
+                fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                    let shape = seq
+                        .next_element()?
+                        .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+                    let data = seq
+                        .next_element()?
+                        .ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+
+                    Ok(ArrayData { shape, data })
+                }
+
+                fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                    let mut shape = None;
+                    let mut data = None;
+
+                    while let Some(key) = map.next_key::<String>()? {
+                        match key.as_str() {
+                            "shape" => shape = Some(map.next_value()?),
+                            "data" => data = Some(map.next_value()?),
+                            _ => {
+                                map.next_value::<serde::de::IgnoredAny>()?;
+                            }
+                        }
+                    }
+
+                    Ok(ArrayData {
+                        shape: shape.ok_or_else(|| serde::de::Error::missing_field("shape"))?,
+                        data: data.ok_or_else(|| serde::de::Error::missing_field("data"))?,
+                    })
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,524 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote};
+
+use crate::manifest::{ArrayType, EntryPoint, Manifest, OpaqueType, Type};
+
+pub fn template(manifest: &Manifest) -> TokenStream {
+    let handler_fields = manifest.entry_points.iter().map(|ep| {
+        let name = ep.context_fn_ident();
+        let handler = handler_type(ep);
+
+        quote!(#name: Option<Arc<dyn #handler + Send + Sync>>)
+    });
+
+    let config_fns = manifest.entry_points.iter().map(config_fn_template);
+
+    let type_impls = manifest.types.iter().map(|typ| match typ {
+        Type::Value(_) => quote!(),
+        Type::Array(array) => impl_array_template(array),
+        Type::Opaque(opaque) => impl_opaque_template(opaque),
+    });
+
+    let entry_impls = manifest.entry_points.iter().map(impl_entry_point_template);
+
+    let opaque_values = manifest.types.iter().map(|typ| match typ {
+        Type::Opaque(opaque) => opaque_value_template(opaque),
+        _ => quote!(),
+    });
+
+    quote! {
+        /// Pure-Rust backend for tests.
+        ///
+        /// This backend does not link any code generated by Futhark.
+        /// Arrays are kept in memory and entry points call the handlers
+        /// registered on the [`Config`](super::Config), like `on_entry_*`.
+        /// Entry points without a handler fail with status `1`,
+        /// as do handlers returning arrays whose data does not match their shape.
+        ///
+        /// Unlike with Futhark, `new_raw` copies its data.
+        ///
+        /// Opaque values are wrapped in [`Opaque`](test::Opaque)
+        /// and can not be stored as bytes.
+        /// Their `from_test_value` constructor creates them without a handler.
+        pub mod test {
+            use std::sync::Arc;
+
+            use super::{types, Backend};
+
+            /// Test backend.
+            ///
+            /// This can be passed to [`Config`](super::super::Config),
+            /// to test code without running Futhark.
+            pub struct Test;
+
+            /// Array as passed to and returned from test handlers.
+            #[derive(Debug, Clone, Default, PartialEq)]
+            pub struct Array<T> {
+                /// Shape of the array.
+                pub shape: Vec<usize>,
+                /// Values of the array in row-major form.
+                pub data: Vec<T>,
+            }
+
+            impl<T> Array<T> {
+                /// Create a new array.
+                ///
+                /// # Panics
+                /// Panics if the length of `data` does not match the `shape`.
+                pub fn new(data: Vec<T>, shape: Vec<usize>) -> Self {
+                    assert_eq!(data.len(), shape.iter().product::<usize>(), "Array data does not match its shape.");
+                    Array { shape, data }
+                }
+
+                /// Whether the array has `rank` dimensions, which fit into an `i64`,
+                /// and exactly as many values as they require.
+                #[allow(dead_code)]
+                fn is_valid(&self, rank: usize) -> bool {
+                    self.shape.len() == rank
+                        && self.shape.iter().all(|&dim| i64::try_from(dim).is_ok())
+                        && self.shape.iter().try_fold(1usize, |len, &dim| len.checked_mul(dim)) == Some(self.data.len())
+                }
+            }
+
+            /// Opaque value as passed to and returned from test handlers.
+            ///
+            /// Handlers can store any value and downcast it again.
+            pub struct Opaque(pub Box<dyn std::any::Any + Send + Sync>);
+
+            struct StoredArray<T> {
+                array: Array<T>,
+                dims: Vec<i64>,
+            }
+
+            impl<T> StoredArray<T> {
+                fn into_raw<U>(array: Array<T>) -> *mut U {
+                    let dims = array.shape.iter().map(|&dim| dim as i64).collect();
+                    Box::into_raw(Box::new(StoredArray { array, dims })) as *mut U
+                }
+
+                unsafe fn get<'a, U>(ptr: *const U) -> &'a StoredArray<T> {
+                    &*(ptr as *const StoredArray<T>)
+                }
+
+                unsafe fn free<U>(ptr: *mut U) {
+                    drop(Box::from_raw(ptr as *mut StoredArray<T>));
+                }
+            }
+
+            #[derive(Clone, Default)]
+            #[allow(unused_parens, clippy::type_complexity)]
+            struct Handlers {
+                #(#handler_fields),*
+            }
+
+            #[derive(Default)]
+            struct TestConfig {
+                handlers: Handlers,
+            }
+
+            struct TestContext {
+                handlers: Handlers,
+            }
+
+            impl super::super::Config<Test> {
+                fn handlers(&mut self) -> &mut Handlers {
+                    unsafe { &mut (*(self.inner as *mut TestConfig)).handlers }
+                }
+
+                #(#config_fns)*
+            }
+
+            impl Backend for Test {
+                type RawMemory = *mut u8;
+
+                unsafe fn futhark_context_config_new() -> *mut types::futhark_context_config {
+                    Box::into_raw(Box::<TestConfig>::default()) as *mut types::futhark_context_config
+                }
+
+                unsafe fn futhark_context_config_free(cfg: *mut types::futhark_context_config) {
+                    drop(Box::from_raw(cfg as *mut TestConfig));
+                }
+
+                unsafe fn futhark_context_new(
+                    cfg: *mut types::futhark_context_config,
+                ) -> *mut types::futhark_context {
+                    let handlers = (*(cfg as *const TestConfig)).handlers.clone();
+                    Box::into_raw(Box::new(TestContext { handlers })) as *mut types::futhark_context
+                }
+
+                unsafe fn futhark_context_free(ctx: *mut types::futhark_context) {
+                    drop(Box::from_raw(ctx as *mut TestContext));
+                }
+
+                unsafe fn futhark_context_sync(
+                    _ctx: *mut types::futhark_context,
+                ) -> ::std::os::raw::c_int {
+                    0
+                }
+
+                #(#type_impls)*
+
+                #(#entry_impls)*
+            }
+
+            #(#opaque_values)*
+        }
+        pub use test::Test;
+    }
+}
+
+fn handler_type(ep: &EntryPoint) -> TokenStream {
+    let inputs = ep.inputs.iter().map(|input| match &input.typ {
+        Type::Value(value) => {
+            let typ = value.ident();
+            quote!(#typ)
+        }
+        Type::Array(array) => {
+            let elem = array.elements_type.ident();
+            quote!(&Array<#elem>)
+        }
+        Type::Opaque(_) => quote!(&Opaque),
+    });
+
+    let outputs = ep.outputs.iter().map(|output| match &output.typ {
+        Type::Value(value) => {
+            let typ = value.ident();
+            quote!(#typ)
+        }
+        Type::Array(array) => {
+            let elem = array.elements_type.ident();
+            quote!(Array<#elem>)
+        }
+        Type::Opaque(_) => quote!(Opaque),
+    });
+
+    quote!(Fn(#(#inputs),*) -> Result<(#(#outputs),*), i32>)
+}
+
+fn config_fn_template(ep: &EntryPoint) -> TokenStream {
+    let entry_name = ep.context_fn_ident();
+    let fn_name = format_ident!("on_{}", entry_name);
+    let handler = handler_type(ep);
+
+    let doc =
+        format!("Handle calls to [`Context::{entry_name}`](super::super::Context::{entry_name}).");
+
+    quote! {
+        #[doc = #doc]
+        ///
+        /// The handler returns the outputs or a non-zero status code.
+        /// A status code of `0` is reported as `1`, because `0` means success.
+        /// Returned arrays whose data does not match their shape fail with status `1` as well.
+        #[allow(unused_parens)]
+        pub fn #fn_name(mut self, handler: impl #handler + Send + Sync + 'static) -> Self {
+            self.handlers().#entry_name = Some(Arc::new(handler));
+            self
+        }
+    }
+}
+
+fn impl_entry_point_template(ep: &EntryPoint) -> TokenStream {
+    let futhark_entry_name = ep.futhark_fn_ident();
+    let entry_name = ep.context_fn_ident();
+
+    let input_names = ep.input_idents();
+    let output_names = ep.output_idents();
+
+    let inputs = ep
+        .inputs
+        .iter()
+        .zip(&input_names)
+        .map(|(input, name)| match &input.typ {
+            Type::Value(value) => {
+                let typ = value.ident();
+                quote!(#name: #typ)
+            }
+            Type::Array(array) => {
+                let typ = array.type_ident();
+                quote!(#name: *const types::#typ)
+            }
+            Type::Opaque(opaque) => {
+                let typ = opaque.type_ident();
+                quote!(#name: *const types::#typ)
+            }
+        });
+
+    let outputs = ep
+        .outputs
+        .iter()
+        .zip(&output_names)
+        .map(|(output, name)| match &output.typ {
+            Type::Value(value) => {
+                let typ = value.ident();
+                quote!(#name: *mut #typ)
+            }
+            Type::Array(array) => {
+                let typ = array.type_ident();
+                quote!(#name: *mut *mut types::#typ)
+            }
+            Type::Opaque(opaque) => {
+                let typ = opaque.type_ident();
+                quote!(#name: *mut *mut types::#typ)
+            }
+        });
+
+    let handler_args = ep
+        .inputs
+        .iter()
+        .zip(&input_names)
+        .map(|(input, name)| match &input.typ {
+            Type::Value(_) => quote!(#name),
+            Type::Array(array) => {
+                let elem = array.elements_type.ident();
+                quote!(&StoredArray::<#elem>::get(#name).array)
+            }
+            Type::Opaque(_) => quote!(&*(#name as *const Opaque)),
+        });
+
+    // `handler` and `outputs` are never used as input or output names.
+    let output_values = (0..ep.outputs.len())
+        .map(|i| {
+            if ep.outputs.len() == 1 {
+                quote!(outputs)
+            } else {
+                let index = Literal::usize_unsuffixed(i);
+                quote!(outputs.#index)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let array_checks = ep
+        .outputs
+        .iter()
+        .zip(&output_values)
+        .filter_map(|(output, value)| match &output.typ {
+            Type::Array(array) => {
+                let rank = array.rank;
+                Some(quote!(#value.is_valid(#rank)))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    // Stored arrays are read with their shape, so it must match their data.
+    let check_outputs = if array_checks.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            if !(#(#array_checks)&&*) {
+                return 1;
+            }
+        }
+    };
+
+    let write_outputs = ep
+        .outputs
+        .iter()
+        .zip(&output_names)
+        .zip(&output_values)
+        .map(|((output, name), value)| match &output.typ {
+            Type::Value(_) => quote!(*#name = #value;),
+            Type::Array(_) => quote!(*#name = StoredArray::into_raw(#value);),
+            Type::Opaque(opaque) => {
+                let typ = opaque.type_ident();
+                quote!(*#name = Box::into_raw(Box::new(#value)) as *mut types::#typ;)
+            }
+        });
+
+    quote! {
+        unsafe fn #futhark_entry_name(ctx: *mut types::futhark_context, #(#outputs),*, #(#inputs),*) -> std::ffi::c_int {
+            let ctx = &*(ctx as *const TestContext);
+            let outputs = match &ctx.handlers.#entry_name {
+                Some(handler) => handler(#(#handler_args),*),
+                None => return 1,
+            };
+
+            match outputs {
+                Ok(outputs) => {
+                    #check_outputs
+                    #(#write_outputs)*
+                    0
+                }
+                // Futhark reports success as `0`, so a handler can not fail with it.
+                Err(0) => 1,
+                Err(status) => status,
+            }
+        }
+    }
+}
+
+fn impl_array_template(array: &ArrayType) -> TokenStream {
+    let name_type = array.type_ident();
+    let name_new = array.fn_new_ident();
+    let name_shape = array.fn_shape_ident();
+    let name_values = array.fn_values_ident();
+    let name_free = array.fn_free_ident();
+    let name_elem = array.elements_type.ident();
+
+    let dims = (0..array.rank)
+        .map(|i| format_ident!("dim_{}", i))
+        .collect::<Vec<_>>();
+
+    let index_fn = if array.ops.index {
+        let name_index = array.fn_index_ident();
+        let indices = (0..array.rank)
+            .map(|i| format_ident!("i{}", i))
+            .collect::<Vec<_>>();
+
+        quote! {
+            unsafe fn #name_index(
+                _ctx: *mut types::futhark_context,
+                out: *mut #name_elem,
+                arr: *mut types::#name_type,
+                #(#indices: i64),*
+            ) -> std::os::raw::c_int {
+                let array = &StoredArray::<#name_elem>::get(arr).array;
+
+                let mut offset = 0;
+                for (index, dim) in [#(#indices),*].into_iter().zip(&array.shape) {
+                    match usize::try_from(index) {
+                        Ok(index) if index < *dim => offset = offset * dim + index,
+                        _ => return 1,
+                    }
+                }
+
+                *out = array.data[offset];
+                0
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let new_raw_fn = if array.ops.new_raw {
+        let name_new_raw = array.fn_new_raw_ident();
+
+        quote! {
+            unsafe fn #name_new_raw(
+                ctx: *mut types::futhark_context,
+                data: Self::RawMemory,
+                #(#dims: i64),*
+            ) -> *mut types::#name_type {
+                // Stored arrays own their data, so this copies like `new`.
+                Self::#name_new(ctx, data as *const #name_elem, #(#dims),*)
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let values_raw_fn = if array.ops.values_raw {
+        let name_values_raw = array.fn_values_raw_ident();
+
+        quote! {
+            unsafe fn #name_values_raw(
+                _ctx: *mut types::futhark_context,
+                arr: *mut types::#name_type,
+            ) -> Self::RawMemory {
+                StoredArray::<#name_elem>::get(arr).array.data.as_ptr() as *mut u8
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    quote! {
+        #index_fn
+        #new_raw_fn
+        #values_raw_fn
+
+        unsafe fn #name_new(
+            _ctx: *mut types::futhark_context,
+            data: *const #name_elem,
+            #(#dims: i64),*
+        ) -> *mut types::#name_type {
+            let shape = vec![#(#dims as usize),*];
+            let data = std::slice::from_raw_parts(data, shape.iter().product()).to_vec();
+
+            StoredArray::into_raw(Array { shape, data })
+        }
+
+        unsafe fn #name_free(
+            _ctx: *mut types::futhark_context,
+            arr: *mut types::#name_type,
+        ) -> std::os::raw::c_int {
+            StoredArray::<#name_elem>::free(arr);
+            0
+        }
+
+        unsafe fn #name_values(
+            _ctx: *mut types::futhark_context,
+            arr: *mut types::#name_type,
+            data: *mut #name_elem,
+        ) -> std::os::raw::c_int {
+            let values = &StoredArray::<#name_elem>::get(arr).array.data;
+            std::ptr::copy_nonoverlapping(values.as_ptr(), data, values.len());
+            0
+        }
+
+        unsafe fn #name_shape(
+            _ctx: *mut types::futhark_context,
+            arr: *mut types::#name_type,
+        ) -> *const i64 {
+            StoredArray::<#name_elem>::get(arr).dims.as_ptr()
+        }
+    }
+}
+
+fn impl_opaque_template(opaque: &OpaqueType) -> TokenStream {
+    let name_type = opaque.type_ident();
+    let name_store = opaque.fn_store_ident();
+    let name_restore = opaque.fn_restore_ident();
+    let name_free = opaque.fn_free_ident();
+
+    quote! {
+        unsafe fn #name_store(
+            _ctx: *mut types::futhark_context,
+            _obj: *const types::#name_type,
+            _data: *mut *mut std::os::raw::c_void,
+            _size: *mut usize,
+        ) -> std::os::raw::c_int {
+            1
+        }
+
+        unsafe fn #name_restore(
+            _ctx: *mut types::futhark_context,
+            _data: *const std::os::raw::c_void,
+        ) -> *mut types::#name_type {
+            std::ptr::null_mut()
+        }
+
+        unsafe fn #name_free(
+            _ctx: *mut types::futhark_context,
+            obj: *mut types::#name_type,
+        ) -> std::os::raw::c_int {
+            drop(Box::from_raw(obj as *mut Opaque));
+            0
+        }
+    }
+}
+
+fn opaque_value_template(opaque: &OpaqueType) -> TokenStream {
+    let struct_name = opaque.struct_ident();
+    let name_type = opaque.type_ident();
+
+    quote! {
+        impl<'c> super::super::#struct_name<'c, Test> {
+            /// Wrap a value like the ones returned by test handlers.
+            ///
+            /// This allows hand-written [`EntryPoints`](super::super::EntryPoints)
+            /// to return opaque values.
+            pub fn from_test_value(context: &'c super::super::Context<Test>, value: Opaque) -> Self {
+                super::super::#struct_name {
+                    context,
+                    inner: Box::into_raw(Box::new(value)) as *mut types::#name_type,
+                }
+            }
+
+            /// Returns the value stored by a test handler or [`from_test_value`](Self::from_test_value).
+            pub fn test_value(&self) -> &Opaque {
+                unsafe { &*(self.inner as *const Opaque) }
+            }
+        }
+    }
+}
@@ -0,0 +1,354 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::manifest::{Manifest, Type, ValueType};
+
+pub fn template(manifest: &Manifest) -> TokenStream {
+    let scalar_impls = ValueType::variants()
+        .iter()
+        .filter(|typ| !matches!(typ, ValueType::f16))
+        .map(scalar_impl_template);
+
+    // Only arrays use these, so they would be dead code in programs without any.
+    let array_helpers = if manifest
+        .types
+        .iter()
+        .any(|typ| matches!(typ, Type::Array(_)))
+    {
+        array_helpers_template()
+    } else {
+        quote!()
+    };
+
+    quote! {
+        /// Futhark's textual value format.
+        ///
+        /// This is the format used by `futhark test`, `futhark dataset` and the Futhark REPL,
+        /// for example `[[1.0f64, 2.0f64], [3.0f64, 4.0f64]]` or `42i32`.
+        ///
+        /// Generated arrays implement [`Display`](std::fmt::Display) and `to_textual` using this format
+        /// and can be parsed from it using their `from_textual` constructor.
+        /// Scalars can be formatted and parsed through [`textual::Value`].
+        pub mod textual {
+            use std::fmt;
+
+            /// Error returned when a value in Futhark's textual format can not be parsed.
+            #[derive(Debug, Clone, PartialEq, Eq)]
+            pub struct ParseError {
+                message: String,
+            }
+
+            impl ParseError {
+                pub(super) fn new(message: impl Into<String>) -> Self {
+                    ParseError {
+                        message: message.into(),
+                    }
+                }
+            }
+
+            impl fmt::Display for ParseError {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    write!(f, "invalid Futhark value: {}", self.message)
+                }
+            }
+
+            impl std::error::Error for ParseError {}
+
+            /// Scalar types that can be written in Futhark's textual value format.
+            ///
+            /// Note that `f16` values are represented as `f32` on the Rust side,
+            /// which means [`Value<f32>`] always uses the `f32` suffix.
+            pub trait Scalar: Copy + std::str::FromStr {
+                /// The Futhark type of the scalar, which is also used as literal suffix.
+                const SUFFIX: &'static str;
+
+                /// Whether the scalar is a floating point number.
+                const IS_FLOAT: bool;
+
+                /// Write the scalar as literal with the given type suffix.
+                fn write_literal(self, f: &mut fmt::Formatter<'_>, suffix: &str) -> fmt::Result;
+
+                /// Returns the special floating point value named `name`, like `nan` or `inf`.
+                fn special(_name: &str) -> Option<Self> {
+                    None
+                }
+            }
+
+            #(#scalar_impls)*
+
+            /// A scalar in Futhark's textual value format.
+            ///
+            /// ```ignore
+            /// assert_eq!(textual::Value(1.5f64).to_string(), "1.5f64");
+            /// assert_eq!("-3i32".parse::<textual::Value<i32>>()?.0, -3);
+            /// ```
+            #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+            pub struct Value<T>(pub T);
+
+            impl<T: Scalar> fmt::Display for Value<T> {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    self.0.write_literal(f, T::SUFFIX)
+                }
+            }
+
+            impl<T: Scalar> std::str::FromStr for Value<T> {
+                type Err = ParseError;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    parse_scalar(s.trim(), T::SUFFIX).map(Value)
+                }
+            }
+
+            pub(crate) fn parse_scalar<T: Scalar>(token: &str, suffix: &str) -> Result<T, ParseError> {
+                if T::IS_FLOAT {
+                    let special = match token.strip_prefix(suffix) {
+                        Some(".nan") => T::special("nan"),
+                        Some(".inf") => T::special("inf"),
+                        _ => match token.strip_prefix('-').and_then(|t| t.strip_prefix(suffix)) {
+                            Some(".inf") => T::special("-inf"),
+                            _ => None,
+                        },
+                    };
+
+                    if let Some(value) = special {
+                        return Ok(value);
+                    }
+                }
+
+                token
+                    .strip_suffix(suffix)
+                    .unwrap_or(token)
+                    .replace('_', "")
+                    .parse()
+                    .map_err(|_| ParseError::new(format!("`{token}` is not a valid {suffix} value")))
+            }
+
+            #array_helpers
+        }
+    }
+}
+
+fn array_helpers_template() -> TokenStream {
+    quote! {
+        pub(crate) fn write_array<T: Scalar>(
+            f: &mut fmt::Formatter<'_>,
+            values: &[T],
+            shape: &[usize],
+            suffix: &str,
+        ) -> fmt::Result {
+            if shape.contains(&0) {
+                f.write_str("empty(")?;
+                for dim in shape {
+                    write!(f, "[{dim}]")?;
+                }
+                return write!(f, "{suffix})");
+            }
+
+            match shape.split_first() {
+                None => values[0].write_literal(f, suffix),
+                Some((&dim, rest)) => {
+                    let stride = values.len() / dim;
+
+                    f.write_str("[")?;
+                    for (i, chunk) in values.chunks(stride).enumerate() {
+                        if i > 0 {
+                            f.write_str(", ")?;
+                        }
+                        write_array(f, chunk, rest, suffix)?;
+                    }
+                    f.write_str("]")
+                }
+            }
+        }
+
+        /// Formats an array with the given shape into a string, see [`write_array`].
+        pub(crate) fn format_array<T: Scalar>(values: &[T], shape: &[usize], suffix: &str) -> String {
+            struct Array<'a, T> {
+                values: &'a [T],
+                shape: &'a [usize],
+                suffix: &'a str,
+            }
+
+            impl<T: Scalar> fmt::Display for Array<'_, T> {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    write_array(f, self.values, self.shape, self.suffix)
+                }
+            }
+
+            Array { values, shape, suffix }.to_string()
+        }
+
+        /// Parses an array of the given rank and returns its flat values and shape.
+        pub(crate) fn parse_array<T: Scalar>(
+            input: &str,
+            rank: usize,
+            suffix: &str,
+        ) -> Result<(Vec<T>, Vec<usize>), ParseError> {
+            let mut parser = Parser {
+                input: input.trim(),
+                suffix,
+                values: Vec::new(),
+                shape: vec![None; rank],
+            };
+
+            if let Some(rest) = parser.input.strip_prefix("empty(") {
+                parser.input = rest;
+                parser.parse_empty()?;
+            } else {
+                parser.parse_value(0)?;
+            }
+
+            if !parser.input.trim().is_empty() {
+                return Err(ParseError::new(format!(
+                    "unexpected trailing input `{}`",
+                    parser.input.trim()
+                )));
+            }
+
+            let shape = parser.shape.into_iter().map(|dim| dim.unwrap_or(0)).collect();
+
+            Ok((parser.values, shape))
+        }
+
+        struct Parser<'a, T> {
+            input: &'a str,
+            suffix: &'a str,
+            values: Vec<T>,
+            shape: Vec<Option<usize>>,
+        }
+
+        impl<T: Scalar> Parser<'_, T> {
+            fn eat(&mut self, token: char) -> bool {
+                self.input = self.input.trim_start();
+                match self.input.strip_prefix(token) {
+                    Some(rest) => {
+                        self.input = rest;
+                        true
+                    }
+                    None => false,
+                }
+            }
+
+            fn expect(&mut self, token: char) -> Result<(), ParseError> {
+                if self.eat(token) {
+                    Ok(())
+                } else {
+                    Err(ParseError::new(format!("expected `{token}`")))
+                }
+            }
+
+            fn parse_value(&mut self, depth: usize) -> Result<(), ParseError> {
+                if depth == self.shape.len() {
+                    self.input = self.input.trim_start();
+                    let end = self
+                        .input
+                        .find(|c: char| c == ',' || c == ']' || c.is_whitespace())
+                        .unwrap_or(self.input.len());
+                    let (token, rest) = self.input.split_at(end);
+                    self.values.push(parse_scalar(token, self.suffix)?);
+                    self.input = rest;
+                    return Ok(());
+                }
+
+                self.expect('[')?;
+
+                let mut len = 0;
+                if !self.eat(']') {
+                    loop {
+                        self.parse_value(depth + 1)?;
+                        len += 1;
+
+                        if self.eat(']') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+
+                if len == 0 && depth + 1 < self.shape.len() {
+                    return Err(ParseError::new("empty arrays of rank above one need `empty(...)`"));
+                }
+
+                match self.shape[depth] {
+                    None => self.shape[depth] = Some(len),
+                    Some(expected) if expected == len => {}
+                    Some(_) => return Err(ParseError::new("irregular array")),
+                }
+
+                Ok(())
+            }
+
+            fn parse_empty(&mut self) -> Result<(), ParseError> {
+                for depth in 0..self.shape.len() {
+                    self.expect('[')?;
+                    let end = self.input.find(']').unwrap_or(self.input.len());
+                    let dim = self.input[..end]
+                        .trim()
+                        .parse()
+                        .map_err(|_| ParseError::new("invalid dimension in `empty(...)`"))?;
+                    self.shape[depth] = Some(dim);
+                    self.input = &self.input[end..];
+                    self.expect(']')?;
+                }
+
+                self.input = self.input.trim_start();
+                match self.input.strip_prefix(self.suffix) {
+                    Some(rest) => self.input = rest,
+                    None => return Err(ParseError::new(format!("expected `{}`", self.suffix))),
+                }
+                self.expect(')')?;
+
+                if self.shape.iter().all(|dim| *dim != Some(0)) {
+                    return Err(ParseError::new("`empty(...)` requires a zero dimension"));
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+fn scalar_impl_template(typ: &ValueType) -> TokenStream {
+    let rust_type = typ.ident();
+    let suffix = typ.name();
+
+    if typ.is_float() {
+        quote! {
+            impl Scalar for #rust_type {
+                const SUFFIX: &'static str = #suffix;
+                const IS_FLOAT: bool = true;
+
+                fn write_literal(self, f: &mut fmt::Formatter<'_>, suffix: &str) -> fmt::Result {
+                    if self.is_nan() {
+                        write!(f, "{suffix}.nan")
+                    } else if self.is_infinite() {
+                        let sign = if self < 0.0 { "-" } else { "" };
+                        write!(f, "{sign}{suffix}.inf")
+                    } else {
+                        write!(f, "{self:?}{suffix}")
+                    }
+                }
+
+                fn special(name: &str) -> Option<Self> {
+                    match name {
+                        "nan" => Some(#rust_type::NAN),
+                        "inf" => Some(#rust_type::INFINITY),
+                        "-inf" => Some(#rust_type::NEG_INFINITY),
+                        _ => None,
+                    }
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl Scalar for #rust_type {
+                const SUFFIX: &'static str = #suffix;
+                const IS_FLOAT: bool = false;
+
+                fn write_literal(self, f: &mut fmt::Formatter<'_>, suffix: &str) -> fmt::Result {
+                    write!(f, "{self}{suffix}")
+                }
+            }
+        }
+    }
+}
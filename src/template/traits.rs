@@ -0,0 +1,73 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::manifest::ValueType;
+
+pub fn template() -> TokenStream {
+    let element_impls = ValueType::variants()
+        .iter()
+        .filter(|typ| !matches!(typ, ValueType::f16))
+        .map(|typ| {
+            let ident = typ.ident();
+            let name = typ.name();
+
+            quote! {
+                impl FutharkElement for #ident {
+                    const NAME: &'static str = #name;
+                }
+            }
+        });
+
+    quote! {
+        /// Element types of Futhark arrays.
+        ///
+        /// This allows writing code that is generic over the element type of arrays.
+        /// `f16` values are represented as `f32`, so use [`FutharkArray::ELEM_NAME`]
+        /// to tell `f16` and `f32` arrays apart.
+        pub trait FutharkElement:
+            Copy + Default + PartialEq + std::fmt::Debug + std::fmt::Display + Send + Sync + 'static
+        {
+            /// Name of the Futhark type, like `f64`.
+            const NAME: &'static str;
+        }
+
+        #(#element_impls)*
+
+        /// Common interface of all generated array types.
+        ///
+        /// This allows writing code that is generic over the element type and rank of arrays.
+        /// The methods behave like their inherent counterparts on the array types.
+        pub trait FutharkArray<'c, B: Backend>: Sized {
+            /// Type of the arrays elements.
+            type Elem: FutharkElement;
+
+            /// Shape of the array, which is `[usize; RANK]`.
+            type Shape: AsRef<[usize]> + Copy + std::fmt::Debug;
+
+            /// Number of dimensions of the array.
+            const RANK: usize;
+
+            /// Name of the Futhark element type, like `f64`.
+            ///
+            /// This is `f16` for `f16` arrays, whose [`Elem`](Self::Elem) is `f32`.
+            const ELEM_NAME: &'static str;
+
+            /// Create a new Futhark array from a flat buffer and a shape.
+            ///
+            /// Multi-dimensional arrays are expect row-major form.
+            fn try_from_shape(context: &'c Context<B>, data: &[Self::Elem], shape: Self::Shape) -> Result<Self, Error>;
+
+            /// Returns the context of the array.
+            fn context(&self) -> &'c Context<B>;
+
+            /// Returns the arrays shape.
+            fn shape(&self) -> Self::Shape;
+
+            /// Read the arrays values into `out`.
+            fn values_into(&self, out: &mut [Self::Elem]) -> Result<(), Error>;
+
+            /// Read the arrays values into a new vector.
+            fn to_vec(&self) -> Result<Vec<Self::Elem>, Error>;
+        }
+    }
+}
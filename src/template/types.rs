@@ -11,6 +11,7 @@ pub fn template(manifest: &Manifest) -> TokenStream {
     let dynamic_types = manifest.types.iter().filter_map(|typ| match typ {
         crate::manifest::Type::Value(_) => None,
         crate::manifest::Type::Array(array) => Some(array.type_ident()),
+        crate::manifest::Type::Opaque(opaque) => Some(opaque.type_ident()),
     });
 
     let structs = std::iter::empty()
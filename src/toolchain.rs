@@ -0,0 +1,352 @@
+//! Checks for the tools and libraries needed to build each [`Target`].
+//!
+//! These checks back the `cargo futhark doctor` command
+//! and explain build failures of the [`Generator`](crate::Generator).
+//! They only look in common locations, so a dependency that is reported
+//! as missing might still be found by the compiler through other means.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::Target;
+
+/// Result of looking for a single dependency.
+#[derive(Debug, Clone)]
+pub struct Requirement {
+    /// Name of the dependency, like `futhark` or `OpenCL headers`.
+    pub name: String,
+    /// Version and location of the dependency, `None` if it is missing.
+    pub found: Option<String>,
+    /// How to install the dependency or make it discoverable.
+    pub hint: &'static str,
+    /// Directories that were searched.
+    pub searched: Vec<PathBuf>,
+}
+
+impl Requirement {
+    /// Whether the dependency could not be found.
+    pub fn is_missing(&self) -> bool {
+        self.found.is_none()
+    }
+}
+
+/// Checks the dependencies needed by every target.
+///
+/// This includes the Futhark compiler, `rustfmt`, a C compiler and libclang for `bindgen`.
+pub fn check_common() -> Vec<Requirement> {
+    vec![
+        tool(
+            "futhark",
+            &["--version"],
+            "Install Futhark from https://futhark-lang.org and add it to PATH.",
+        ),
+        tool(
+            "rustfmt",
+            &["--version"],
+            "Install it with `rustup component add rustfmt`.",
+        ),
+        tool(
+            env::var("CC").ok().as_deref().unwrap_or("cc"),
+            &["--version"],
+            "Install a C compiler like gcc or clang, or set CC.",
+        ),
+        libclang(),
+    ]
+}
+
+/// Checks the additional dependencies of `target`.
+///
+/// `cuda_home` is searched first for the CUDA SDK, like with
+/// [`Generator::with_cuda_home`](crate::Generator::with_cuda_home).
+pub fn check_target(target: Target, cuda_home: Option<&Path>) -> Vec<Requirement> {
+    match target {
+        Target::C | Target::MultiCore => Vec::new(),
+        Target::OpenCL => vec![
+            header(
+                "OpenCL headers",
+                "CL/cl.h",
+                include_dirs(),
+                "Install the OpenCL headers, like `opencl-headers` or `opencl-c-headers`.",
+            ),
+            library(
+                "OpenCL loader",
+                "OpenCL",
+                library_dirs(),
+                "Install an OpenCL ICD loader, like `ocl-icd-opencl-dev`.",
+            ),
+        ],
+        Target::Cuda => {
+            let homes = cuda_homes(cuda_home);
+            let include_dirs = homes.iter().map(|home| home.join("include")).collect();
+            let lib_dirs = homes
+                .iter()
+                .map(|home| home.join("lib64"))
+                .chain(library_dirs())
+                .collect::<Vec<_>>();
+            let hint = "Install the CUDA SDK and set CUDA_HOME or use `Generator::with_cuda_home`.";
+
+            vec![
+                header("CUDA headers", "cuda.h", include_dirs, hint),
+                library("CUDA runtime", "cudart", lib_dirs.clone(), hint),
+                library("NVRTC", "nvrtc", lib_dirs.clone(), hint),
+                library(
+                    "CUDA driver",
+                    "cuda",
+                    lib_dirs,
+                    "Install the Nvidia driver, which provides libcuda.",
+                ),
+            ]
+        }
+        Target::ISPC => vec![tool(
+            "ispc",
+            &["--version"],
+            "Install the Intel ISPC compiler and add it to PATH.",
+        )],
+    }
+}
+
+/// Returns the missing dependencies of `targets`, including the common ones.
+pub fn missing(
+    targets: impl IntoIterator<Item = Target>,
+    cuda_home: Option<&Path>,
+) -> Vec<Requirement> {
+    check_common()
+        .into_iter()
+        .chain(
+            targets
+                .into_iter()
+                .flat_map(|target| check_target(target, cuda_home)),
+        )
+        .filter(Requirement::is_missing)
+        .collect()
+}
+
+fn tool(program: &str, version_args: &[&str], hint: &'static str) -> Requirement {
+    let searched = env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let found = Command::new(program)
+        .args(version_args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            let text = String::from_utf8_lossy(&output.stdout).into_owned()
+                + &String::from_utf8_lossy(&output.stderr);
+            let version = text
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .unwrap_or(program)
+                .to_string();
+
+            match find_file(&searched, |name| {
+                name == program || name == format!("{program}.exe")
+            }) {
+                Some(path) => format!("{version} ({})", path.display()),
+                None => version,
+            }
+        });
+
+    Requirement {
+        name: program.to_string(),
+        found,
+        hint,
+        searched,
+    }
+}
+
+fn header(name: &'static str, file: &str, dirs: Vec<PathBuf>, hint: &'static str) -> Requirement {
+    let found = dirs
+        .iter()
+        .map(|dir| dir.join(file))
+        .find(|path| path.is_file())
+        .map(|path| path.display().to_string());
+
+    Requirement {
+        name: name.to_string(),
+        found,
+        hint,
+        searched: dirs,
+    }
+}
+
+fn library(name: &'static str, lib: &str, dirs: Vec<PathBuf>, hint: &'static str) -> Requirement {
+    let found =
+        find_file(&dirs, |file| is_library(file, lib)).map(|path| path.display().to_string());
+
+    Requirement {
+        name: name.to_string(),
+        found,
+        hint,
+        searched: dirs,
+    }
+}
+
+fn libclang() -> Requirement {
+    let mut dirs = env::var_os("LIBCLANG_PATH")
+        .map(|path| env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    if let Ok(output) = Command::new("llvm-config").arg("--libdir").output() {
+        let libdir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if output.status.success() && !libdir.is_empty() {
+            dirs.push(PathBuf::from(libdir));
+        }
+    }
+
+    // Debian and Ubuntu install every LLVM version into its own directory.
+    for parent in ["/usr/lib", "/usr/lib64"] {
+        if let Ok(entries) = fs::read_dir(parent) {
+            let mut llvm_dirs = entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_name().to_string_lossy().starts_with("llvm"))
+                .map(|entry| entry.path().join("lib"))
+                .collect::<Vec<_>>();
+            llvm_dirs.sort();
+            dirs.extend(llvm_dirs);
+        }
+    }
+
+    dirs.extend(library_dirs());
+
+    let mut seen = Vec::new();
+    dirs.retain(|dir| {
+        let new = !seen.contains(dir);
+        seen.push(dir.clone());
+        new
+    });
+
+    library(
+        "libclang",
+        "clang",
+        dirs,
+        "Install libclang (needed by bindgen), like `libclang-dev`, or set LIBCLANG_PATH.",
+    )
+}
+
+/// Checks whether `file` is a shared library named `lib`, like `libOpenCL.so.1`.
+fn is_library(file: &str, lib: &str) -> bool {
+    let Some(rest) = file.strip_prefix("lib").unwrap_or(file).strip_prefix(lib) else {
+        return false;
+    };
+
+    // Versioned names like `libclang-14.so` or `libclang.so.14`.
+    let rest = rest
+        .strip_prefix('-')
+        .unwrap_or(rest)
+        .trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+
+    rest.starts_with("so") || ["dylib", "dll", "lib"].contains(&rest)
+}
+
+fn find_file(dirs: &[PathBuf], matches: impl Fn(&str) -> bool) -> Option<PathBuf> {
+    dirs.iter().find_map(|dir| {
+        let mut entries = fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| matches(&entry.file_name().to_string_lossy()))
+            .map(|entry| entry.path())
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries.into_iter().next()
+    })
+}
+
+fn env_dirs(vars: &[&str]) -> Vec<PathBuf> {
+    vars.iter()
+        .filter_map(env::var_os)
+        .flat_map(|paths| env::split_paths(&paths).collect::<Vec<_>>())
+        .collect()
+}
+
+fn include_dirs() -> Vec<PathBuf> {
+    let mut dirs = env_dirs(&["CPATH", "C_INCLUDE_PATH"]);
+    dirs.extend(
+        [
+            "/usr/include",
+            "/usr/local/include",
+            "/opt/homebrew/include",
+        ]
+        .into_iter()
+        .map(PathBuf::from),
+    );
+    dirs
+}
+
+fn library_dirs() -> Vec<PathBuf> {
+    let mut dirs = env_dirs(&["LIBRARY_PATH", "LD_LIBRARY_PATH", "DYLD_LIBRARY_PATH"]);
+    dirs.extend(
+        [
+            "/usr/lib",
+            "/usr/lib64",
+            "/usr/local/lib",
+            "/usr/lib/x86_64-linux-gnu",
+            "/usr/lib/aarch64-linux-gnu",
+            "/opt/homebrew/lib",
+        ]
+        .into_iter()
+        .map(PathBuf::from),
+    );
+    dirs
+}
+
+fn cuda_homes(cuda_home: Option<&Path>) -> Vec<PathBuf> {
+    cuda_home
+        .map(Path::to_path_buf)
+        .into_iter()
+        .chain(
+            ["CUDA_HOME", "CUDA_PATH"]
+                .into_iter()
+                .filter_map(env::var_os)
+                .map(PathBuf::from),
+        )
+        .chain([PathBuf::from("/usr/local/cuda"), PathBuf::from("/opt/cuda")])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_libraries() {
+        assert!(is_library("libOpenCL.so.1", "OpenCL"));
+        assert!(is_library("libclang-14.so", "clang"));
+        assert!(is_library("libclang.so.14", "clang"));
+        assert!(is_library("OpenCL.lib", "OpenCL"));
+        assert!(is_library("libcudart.so", "cudart"));
+        assert!(is_library("libcuda.dylib", "cuda"));
+
+        assert!(!is_library("libcudart.so", "cuda"));
+        assert!(!is_library("libclang-cpp.so", "clang"));
+        assert!(!is_library("libOpenCL.a", "OpenCL"));
+    }
+
+    #[test]
+    fn finds_files_in_the_first_matching_dir() -> std::io::Result<()> {
+        let dir = env::temp_dir().join(format!("cargo-futhark-toolchain-{}", std::process::id()));
+        let (first, second) = (dir.join("first"), dir.join("second"));
+        fs::create_dir_all(&first)?;
+        fs::create_dir_all(&second)?;
+        fs::write(first.join("libcudart.so"), "")?;
+        fs::write(second.join("libcuda.so.1"), "")?;
+        fs::write(second.join("libcuda.so"), "")?;
+
+        let dirs = [dir.join("missing"), first, second.clone()];
+        let cuda = find_file(&dirs, |file| is_library(file, "cuda"));
+        let cudart = find_file(&dirs, |file| is_library(file, "cudart"));
+        let opencl = find_file(&dirs, |file| is_library(file, "OpenCL"));
+        fs::remove_dir_all(&dir)?;
+
+        assert_eq!(cuda, Some(second.join("libcuda.so")));
+        assert_eq!(cudart, Some(dir.join("first").join("libcudart.so")));
+        assert_eq!(opencl, None);
+
+        Ok(())
+    }
+}
@@ -7,5 +7,8 @@ fn main() -> Result<()> {
         .with_target_if(Target::OpenCL, cfg!(feature = "opencl"))
         .with_target_if(Target::Cuda, cfg!(feature = "cuda"))
         .with_target_if(Target::ISPC, cfg!(feature = "ispc"))
+        .ndarray(cfg!(feature = "ndarray"))
+        .serde(cfg!(feature = "serde"))
+        .test_backend(cfg!(feature = "test-backend"))
         .run()
 }
@@ -0,0 +1,22 @@
+//! Futhark sample application.
+
+#[allow(dead_code, unused_imports)]
+mod futhark {
+    include!(concat!(env!("OUT_DIR"), "/futhark/futhark_lib.rs"));
+}
+
+use futhark::{backends, Array_F64_1D, Config, Context};
+
+type Backend = backends::{{backend-type}};
+
+fn main() {
+    let config = Config::<Backend>::new();
+    let context = Context::new(config);
+
+    let input = &[1.0, 2.0, 3.0];
+    let input = Array_F64_1D::new(&context, input, input.len());
+    let double = context.entry_double(&input).unwrap();
+    let average = context.entry_average(&double).unwrap();
+
+    println!("result: {}", average);
+}
@@ -0,0 +1,14 @@
+use cargo_futhark::{Generator, Result, Target};
+
+fn main() -> Result<()> {
+    Generator::new("src/lib.fut")
+        .with_target_if(Target::C, cfg!(feature = "c"))
+        .with_target_if(Target::MultiCore, cfg!(feature = "multicore"))
+        .with_target_if(Target::OpenCL, cfg!(feature = "opencl"))
+        .with_target_if(Target::Cuda, cfg!(feature = "cuda"))
+        .with_target_if(Target::ISPC, cfg!(feature = "ispc"))
+        .ndarray(cfg!(feature = "ndarray"))
+        .serde(cfg!(feature = "serde"))
+        .test_backend(cfg!(feature = "test-backend"))
+        .run()
+}
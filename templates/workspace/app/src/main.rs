@@ -0,0 +1,15 @@
+use {{crate_name}}_lib::{backends, Array_F64_1D, Config, Context};
+
+type Backend = backends::{{backend-type}};
+
+fn main() {
+    let config = Config::<Backend>::new();
+    let context = Context::new(config);
+
+    let input = &[1.0, 2.0, 3.0];
+    let input = Array_F64_1D::new(&context, input, input.len());
+    let double = context.entry_double(&input).unwrap();
+    let average = context.entry_average(&double).unwrap();
+
+    println!("result: {}", average);
+}
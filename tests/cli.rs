@@ -0,0 +1,441 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use eyre::{ensure, Context, Result};
+
+fn project_dir(name: &str) -> Result<PathBuf> {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+
+    if dir.exists() {
+        fs::remove_dir_all(&dir).wrap_err("Failed to remove existing project directory.")?;
+    }
+    fs::create_dir_all(dir.join("src")).wrap_err("Failed to create project directory.")?;
+
+    Ok(dir)
+}
+
+fn cargo_futhark(dir: &Path, args: &[&str]) -> Result<bool> {
+    let status = Command::new(env!("CARGO_BIN_EXE_cargo-futhark"))
+        .current_dir(dir)
+        .arg("futhark")
+        .args(args)
+        .status()
+        .wrap_err("Failed to run cargo-futhark.")?;
+
+    Ok(status.success())
+}
+
+#[test]
+fn init_existing_package() -> Result<()> {
+    let dir = project_dir("init-existing")?;
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"existing\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n# Keep me.\n[dependencies]\nserde = \"1\"\n",
+    )?;
+    fs::write(
+        dir.join("build.rs"),
+        "fn main() {\n    println!(\"cargo:rerun-if-changed=build.rs\");\n}\n",
+    )?;
+    fs::write(dir.join("src").join("main.rs"), "fn main() {}\n")?;
+
+    ensure!(cargo_futhark(&dir, &["init"])?, "init failed");
+
+    let cargo_toml = fs::read_to_string(dir.join("Cargo.toml"))?;
+    assert!(cargo_toml.contains("# Keep me."));
+    assert!(cargo_toml.contains("serde = \"1\""));
+    assert!(cargo_toml.contains("default = [\"c\"]"));
+    assert!(cargo_toml.contains("cargo-futhark = "));
+
+    let build_rs = fs::read_to_string(dir.join("build.rs"))?;
+    assert!(build_rs.contains("cargo_futhark::Generator::new(\"src/lib.fut\")"));
+    assert!(build_rs.contains("cargo:rerun-if-changed=build.rs"));
+
+    assert!(dir.join("src").join("lib.fut").is_file());
+    assert!(!dir.join("src").join("lib.rs").exists());
+    let main_rs = fs::read_to_string(dir.join("src").join("main.rs"))?;
+    assert!(main_rs.contains("mod futhark {"));
+
+    Ok(())
+}
+
+#[test]
+fn init_is_idempotent() -> Result<()> {
+    let dir = project_dir("init-twice")?;
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"twice\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )?;
+
+    ensure!(cargo_futhark(&dir, &["init"])?, "first init failed");
+    let files = ["Cargo.toml", "build.rs", "src/lib.rs", "src/lib.fut"]
+        .map(|file| fs::read_to_string(dir.join(file)).unwrap());
+
+    ensure!(cargo_futhark(&dir, &["init"])?, "second init failed");
+    let files_again = ["Cargo.toml", "build.rs", "src/lib.rs", "src/lib.fut"]
+        .map(|file| fs::read_to_string(dir.join(file)).unwrap());
+
+    assert_eq!(files, files_again);
+
+    Ok(())
+}
+
+#[test]
+fn init_requires_cargo_toml() -> Result<()> {
+    let dir = project_dir("init-missing")?;
+
+    assert!(!cargo_futhark(&dir, &["init"])?);
+
+    Ok(())
+}
+
+#[test]
+fn init_rejects_virtual_manifest() -> Result<()> {
+    let dir = project_dir("init-virtual")?;
+    let cargo_toml = "[workspace]\nmembers = [\"app\"]\n";
+    fs::write(dir.join("Cargo.toml"), cargo_toml)?;
+
+    assert!(!cargo_futhark(&dir, &["init"])?);
+    assert_eq!(fs::read_to_string(dir.join("Cargo.toml"))?, cargo_toml);
+
+    Ok(())
+}
+
+#[test]
+fn init_requires_main_in_build_script() -> Result<()> {
+    let dir = project_dir("init-no-main")?;
+    let cargo_toml = "[package]\nname = \"no-main\"\nversion = \"0.1.0\"\nedition = \"2021\"\n";
+    fs::write(dir.join("Cargo.toml"), cargo_toml)?;
+    fs::write(dir.join("build.rs"), "// fn main() {}\n")?;
+
+    assert!(!cargo_futhark(&dir, &["init"])?);
+    assert_eq!(fs::read_to_string(dir.join("Cargo.toml"))?, cargo_toml);
+    assert!(!dir.join("src").join("lib.fut").exists());
+
+    Ok(())
+}
+
+#[test]
+fn new_registers_workspace_member() -> Result<()> {
+    let dir = project_dir("new-workspace")?;
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[workspace]\n# Existing members.\nmembers = [\"app\"]\n",
+    )?;
+
+    ensure!(cargo_futhark(&dir, &["new", "futhark-lib"])?, "new failed");
+
+    let cargo_toml = fs::read_to_string(dir.join("Cargo.toml"))?;
+    assert!(cargo_toml.contains("# Existing members."));
+    assert!(cargo_toml.contains(r#"members = ["app", "futhark-lib"]"#));
+
+    Ok(())
+}
+
+#[test]
+fn new_keeps_plain_packages() -> Result<()> {
+    let dir = project_dir("new-package")?;
+    let manifest = "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n";
+    fs::write(dir.join("Cargo.toml"), manifest)?;
+    fs::write(dir.join("src").join("main.rs"), "fn main() {}\n")?;
+
+    ensure!(cargo_futhark(&dir, &["new", "futhark-lib"])?, "new failed");
+
+    assert_eq!(fs::read_to_string(dir.join("Cargo.toml"))?, manifest);
+
+    Ok(())
+}
+
+#[test]
+fn new_registers_in_workspace_root_from_member() -> Result<()> {
+    let dir = project_dir("new-from-member")?;
+    fs::write(dir.join("Cargo.toml"), "[workspace]\nmembers = [\"app\"]\n")?;
+
+    let app = dir.join("app");
+    fs::create_dir_all(app.join("src"))?;
+    let app_manifest = "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n";
+    fs::write(app.join("Cargo.toml"), app_manifest)?;
+    fs::write(app.join("src").join("main.rs"), "fn main() {}\n")?;
+
+    ensure!(cargo_futhark(&app, &["new", "futhark-lib"])?, "new failed");
+
+    assert_eq!(fs::read_to_string(app.join("Cargo.toml"))?, app_manifest);
+    let cargo_toml = fs::read_to_string(dir.join("Cargo.toml"))?;
+    assert!(cargo_toml.contains(r#"members = ["app", "app/futhark-lib"]"#));
+
+    Ok(())
+}
+
+#[test]
+fn new_skips_covered_and_opted_out_workspaces() -> Result<()> {
+    let dir = project_dir("new-covered")?;
+    let manifest = "[workspace]\nmembers = [\"libs/*\"]\n";
+    fs::write(dir.join("Cargo.toml"), manifest)?;
+
+    fs::create_dir(dir.join("libs"))?;
+    ensure!(
+        cargo_futhark(&dir.join("libs"), &["new", "covered"])?,
+        "new failed"
+    );
+    ensure!(
+        cargo_futhark(&dir, &["new", "--no-workspace", "other"])?,
+        "new failed"
+    );
+
+    assert_eq!(fs::read_to_string(dir.join("Cargo.toml"))?, manifest);
+
+    Ok(())
+}
+
+#[test]
+fn new_lib_template() -> Result<()> {
+    let dir = project_dir("new-lib")?;
+
+    ensure!(
+        cargo_futhark(&dir, &["new", "--no-workspace", "my-lib"])?,
+        "new failed"
+    );
+
+    let project = dir.join("my-lib");
+    let cargo_toml = fs::read_to_string(project.join("Cargo.toml"))?;
+    assert!(cargo_toml.contains(r#"name = "my-lib""#));
+    assert!(!project.join("Cargo.toml.in").exists());
+    assert!(project.join("build.rs").is_file());
+    assert!(project.join("src").join("lib.fut").is_file());
+    assert!(project.join("src").join("lib.rs").is_file());
+
+    Ok(())
+}
+
+#[test]
+fn new_bin_template() -> Result<()> {
+    let dir = project_dir("new-bin")?;
+
+    ensure!(
+        cargo_futhark(
+            &dir,
+            &["new", "--no-workspace", "--template", "bin", "my-app"]
+        )?,
+        "new failed"
+    );
+
+    let project = dir.join("my-app");
+    let cargo_toml = fs::read_to_string(project.join("Cargo.toml"))?;
+    assert!(cargo_toml.contains(r#"name = "my-app""#));
+    assert!(project.join("build.rs").is_file());
+    assert!(project.join("src").join("lib.fut").is_file());
+    assert!(project.join("src").join("main.rs").is_file());
+    assert!(!project.join("src").join("lib.rs").exists());
+
+    Ok(())
+}
+
+#[test]
+fn new_workspace_template() -> Result<()> {
+    let dir = project_dir("new-workspace-template")?;
+    fs::write(dir.join("Cargo.toml"), "[workspace]\nmembers = []\n")?;
+
+    ensure!(
+        cargo_futhark(&dir, &["new", "--template", "workspace", "my-app"])?,
+        "new failed"
+    );
+
+    let project = dir.join("my-app");
+    let cargo_toml = fs::read_to_string(project.join("Cargo.toml"))?;
+    assert!(cargo_toml.contains(r#"members = ["app", "lib"]"#));
+
+    let app_cargo_toml = fs::read_to_string(project.join("app").join("Cargo.toml"))?;
+    assert!(app_cargo_toml.contains(r#"name = "my-app""#));
+    assert!(app_cargo_toml.contains(r#"my-app-lib = { path = "../lib" }"#));
+    let app_main_rs = fs::read_to_string(project.join("app").join("src").join("main.rs"))?;
+    assert!(app_main_rs.contains("use my_app_lib::"));
+
+    let lib_cargo_toml = fs::read_to_string(project.join("lib").join("Cargo.toml"))?;
+    assert!(lib_cargo_toml.contains(r#"name = "my-app-lib""#));
+    assert!(project.join("lib").join("build.rs").is_file());
+
+    assert_eq!(
+        fs::read_to_string(dir.join("Cargo.toml"))?,
+        "[workspace]\nmembers = []\n",
+        "nested workspaces must not be registered"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn new_fills_in_template_variables() -> Result<()> {
+    let dir = project_dir("new-variables")?;
+
+    ensure!(
+        cargo_futhark(
+            &dir,
+            &[
+                "new",
+                "--no-workspace",
+                "--template",
+                "bin",
+                "--backend",
+                "multicore",
+                "my-app",
+            ]
+        )?,
+        "new failed"
+    );
+
+    let project = dir.join("my-app");
+    let cargo_toml = fs::read_to_string(project.join("Cargo.toml"))?;
+    assert!(cargo_toml.contains(r#"edition = "2021""#));
+    assert!(cargo_toml.contains(r#"default = ["multicore"]"#));
+    assert!(cargo_toml.contains("authors = ["));
+
+    let main_rs = fs::read_to_string(project.join("src").join("main.rs"))?;
+    assert!(main_rs.contains("type Backend = backends::MultiCore;"));
+
+    for file in ["Cargo.toml", "build.rs", "src/lib.fut", "src/main.rs"] {
+        let content = fs::read_to_string(project.join(file))?;
+        assert!(!content.contains("{{"), "{file} contains a placeholder");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn new_rejects_invalid_names() -> Result<()> {
+    let dir = project_dir("new-invalid")?;
+
+    for name in ["1st", "fn", "my.lib", "test", "CON"] {
+        assert!(
+            !cargo_futhark(&dir, &["new", "--no-workspace", name])?,
+            "'{name}' was accepted"
+        );
+        assert!(!dir.join(name).exists());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn check_new_project() -> Result<()> {
+    let dir = project_dir("check-new")?;
+
+    ensure!(
+        cargo_futhark(&dir, &["new", "--no-workspace", "checked"])?,
+        "new failed"
+    );
+    ensure!(
+        cargo_futhark(&dir.join("checked"), &["check"])?,
+        "check failed"
+    );
+
+    fs::write(
+        dir.join("checked").join("src").join("lib.fut"),
+        "entry broken (x: i32) : bool = x + 1\n",
+    )?;
+    assert!(!cargo_futhark(&dir.join("checked"), &["check"])?);
+
+    Ok(())
+}
+
+#[test]
+fn check_uses_configured_source() -> Result<()> {
+    let dir = project_dir("check-configured")?;
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"configured\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[package.metadata.futhark]\nsource = \"futhark/main.fut\"\n",
+    )?;
+
+    assert!(!cargo_futhark(&dir, &["check"])?, "missing source accepted");
+
+    fs::create_dir(dir.join("futhark"))?;
+    fs::write(
+        dir.join("futhark").join("main.fut"),
+        "entry double (x: i32) = x * 2\n",
+    )?;
+    ensure!(cargo_futhark(&dir, &["check"])?, "check failed");
+
+    Ok(())
+}
+
+#[test]
+fn clean_removes_futhark_artifacts() -> Result<()> {
+    let dir = project_dir("clean")?;
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"cleaned\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[package.metadata.futhark]\nsource = \"src/lib.fut\"\n\n[workspace]\n",
+    )?;
+    fs::write(dir.join("src").join("lib.rs"), "")?;
+    fs::write(dir.join("src").join("lib.fut"), "entry id (x: i32) = x\n")?;
+    fs::write(dir.join("src").join("lib.cache"), "")?;
+    fs::write(dir.join("kernels.cache"), "")?;
+
+    let debug = dir.join("target").join("debug");
+    let futhark_unit = debug.join("build").join("cleaned-0123456789abcdef");
+    let other_unit = debug.join("build").join("cleaned-fedcba9876543210");
+    let other_package = debug.join("build").join("other-0123456789abcdef");
+    for unit in [&futhark_unit, &other_package] {
+        fs::create_dir_all(unit.join("out").join("futhark_raw"))?;
+    }
+    fs::create_dir_all(&other_unit)?;
+    fs::create_dir_all(debug.join(".fingerprint").join("cleaned-0123456789abcdef"))?;
+
+    ensure!(
+        cargo_futhark(&dir, &["clean", "--dry-run"])?,
+        "dry run failed"
+    );
+    assert!(futhark_unit.exists());
+    assert!(dir.join("kernels.cache").exists());
+
+    ensure!(
+        cargo_futhark(&dir, &["clean", "--cache-file", "kernels.cache"])?,
+        "clean failed"
+    );
+    assert!(!futhark_unit.exists());
+    assert!(!debug
+        .join(".fingerprint")
+        .join("cleaned-0123456789abcdef")
+        .exists());
+    assert!(!dir.join("kernels.cache").exists());
+    assert!(dir.join("src").join("lib.cache").exists());
+    assert!(other_unit.exists());
+    assert!(other_package.exists());
+
+    Ok(())
+}
+
+#[test]
+fn clean_matches_the_whole_package_name() -> Result<()> {
+    let dir = project_dir("clean-prefix")?;
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[workspace]\nmembers = [\"my-lib\", \"my-lib-extra\"]\n",
+    )?;
+    for (package, source) in [("my-lib", "src/lib.fut"), ("my-lib-extra", "src/extra.fut")] {
+        let package_dir = dir.join(package);
+        fs::create_dir_all(package_dir.join("src"))?;
+        fs::write(
+            package_dir.join("Cargo.toml"),
+            format!("[package]\nname = \"{package}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[package.metadata.futhark]\nsource = \"{source}\"\n"),
+        )?;
+        fs::write(package_dir.join("src").join("lib.rs"), "")?;
+        fs::write(package_dir.join(source), "entry id (x: i32) = x\n")?;
+    }
+
+    let build = dir.join("target").join("debug").join("build");
+    let lib_unit = build.join("my-lib-0123456789abcdef");
+    let extra_unit = build.join("my-lib-extra-0123456789abcdef");
+    for unit in [&lib_unit, &extra_unit] {
+        fs::create_dir_all(unit.join("out").join("futhark"))?;
+    }
+
+    ensure!(
+        cargo_futhark(&dir.join("my-lib"), &["clean"])?,
+        "clean failed"
+    );
+    assert!(!lib_unit.exists());
+    assert!(extra_unit.exists());
+
+    Ok(())
+}
@@ -1,16 +1,20 @@
-use std::{fs, path::Path, process::Command};
+use std::{fs, path::Path, process::Command, sync::Mutex};
 
 use cargo_futhark::{Generator, Target};
 use eyre::{ensure, Context, Result};
 
 mod common;
 
+/// The generator tests share `OUT_DIR`, so they must not run at the same time.
+static OUT_DIR_LOCK: Mutex<()> = Mutex::new(());
+
 #[test]
 fn generate_c_target() -> Result<()> {
+    let _lock = OUT_DIR_LOCK.lock().unwrap_or_else(|err| err.into_inner());
     common::setup_env();
     common::create_out_dir()?;
 
-    Generator::new("project-template/src/lib.fut")
+    Generator::new("templates/lib/src/lib.fut")
         .with_target(Target::C)
         .watch_sources(false)
         .run()
@@ -19,7 +23,7 @@ fn generate_c_target() -> Result<()> {
 #[test]
 fn simple_example_matches_template() {
     let example_dir = Path::new("examples").join("simple-lib");
-    let template_dir = Path::new("project-template");
+    let template_dir = Path::new("templates").join("lib");
 
     let example_lib_fut = example_dir.join("src").join("lib.fut");
     let example_lib_rs = example_dir.join("src").join("lib.rs");
@@ -48,6 +52,20 @@ fn simple_example_matches_template() {
     );
 }
 
+#[test]
+fn bin_template_matches_lib_template() {
+    let lib_dir = Path::new("templates").join("lib");
+    let bin_dir = Path::new("templates").join("bin");
+
+    for file in ["Cargo.toml.in", "build.rs", "src/lib.fut"] {
+        assert_eq!(
+            fs::read_to_string(lib_dir.join(file)).unwrap(),
+            fs::read_to_string(bin_dir.join(file)).unwrap(),
+            "{file} differs"
+        );
+    }
+}
+
 #[test]
 fn run_c_target() -> Result<()> {
     let output = Command::new("cargo")
@@ -70,3 +88,41 @@ fn run_c_target() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn generate_output_structs() -> Result<()> {
+    let _lock = OUT_DIR_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    common::setup_env();
+    common::create_out_dir()?;
+
+    Generator::new("templates/lib/src/lib.fut")
+        .with_target(Target::C)
+        .output_structs(true)
+        .watch_sources(false)
+        .run()?;
+
+    let generated = fs::read_to_string(
+        Path::new(env!("CARGO_TARGET_TMPDIR"))
+            .join("futhark")
+            .join("futhark_lib.rs"),
+    )?;
+
+    ensure!(
+        generated.contains("pub struct EntrySwapOutput {"),
+        "missing output struct for `swap`"
+    );
+    ensure!(
+        generated.contains("pub out_0: f64,") && generated.contains("pub out_1: f64,"),
+        "missing positional output fields"
+    );
+    ensure!(
+        generated.contains("-> Result<EntrySwapOutput, "),
+        "`entry_swap` does not return the output struct"
+    );
+    ensure!(
+        !generated.contains("struct EntryAverageOutput"),
+        "single outputs must not get a struct"
+    );
+
+    Ok(())
+}